@@ -48,6 +48,8 @@ else
   echo "Secret jw-news-reader-api-key-auth-cred already exists; not regenerating."
 fi
 
-# Attach plugin to the Service (no Ingress changes)
+# Attach plugin to the Service (no Ingress changes). The
+# jw-news-reader-api-images Service stays unauthenticated on purpose: it only
+# serves mirrored images under /images.
 "${kubectl_base[@]}" -n "${NAMESPACE}" annotate service jw-news-reader-api \
   konghq.com/plugins=jw-news-reader-api-key-auth --overwrite
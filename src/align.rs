@@ -0,0 +1,303 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::extract::ExtractResult;
+use crate::models::AlignedPair;
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Variance of the length ratio between translated paragraphs (Gale & Church).
+const LENGTH_VARIANCE: f64 = 6.8;
+/// Extra cost of merging two paragraphs on one side (1-2 / 2-1).
+const MERGE_PENALTY: f64 = 2.3;
+/// Cost of leaving a paragraph unmatched (1-0 / 0-1).
+const SKIP_PENALTY: f64 = 4.5;
+
+static IMAGE_MD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^!\[[^\]]*\]\([^)]*\)$").unwrap());
+static CAPTION_MD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\*[^*].*\*$").unwrap());
+
+// ── Blocks ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Heading(usize),
+    /// An image, together with its caption if one follows it.
+    Image,
+    Paragraph,
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    kind: BlockKind,
+    texts: Vec<String>,
+}
+
+impl Block {
+    fn len(&self) -> usize {
+        self.texts.iter().map(|t| t.chars().count()).sum()
+    }
+
+    fn is_anchor(&self) -> bool {
+        self.kind != BlockKind::Paragraph
+    }
+}
+
+/// Split extracted markdown into headings, images (with captions) and
+/// paragraph-like blocks.
+fn blocks(markdown: &str) -> Vec<Block> {
+    let mut result: Vec<Block> = Vec::new();
+    for chunk in markdown
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        let heading_level = chunk.chars().take_while(|c| *c == '#').count();
+        let kind = if heading_level > 0 && chunk[heading_level..].starts_with(' ') {
+            BlockKind::Heading(heading_level)
+        } else if IMAGE_MD_RE.is_match(chunk) {
+            BlockKind::Image
+        } else if CAPTION_MD_RE.is_match(chunk)
+            && result
+                .last()
+                .map(|b| b.kind == BlockKind::Image && b.texts.len() == 1)
+                == Some(true)
+        {
+            result.last_mut().unwrap().texts.push(chunk.to_string());
+            continue;
+        } else {
+            BlockKind::Paragraph
+        };
+        result.push(Block {
+            kind,
+            texts: vec![chunk.to_string()],
+        });
+    }
+    result
+}
+
+// ── Alignment ────────────────────────────────────────────────────────────────
+
+/// Align two editions of the same article without translation: headings and
+/// images act as anchors matched in order by kind, and the paragraphs between
+/// consecutive anchors are aligned by length (Gale & Church), allowing 1-1,
+/// 1-2, 2-1 and unmatched paragraphs.
+pub fn align(left: &ExtractResult, right: &ExtractResult) -> Vec<AlignedPair> {
+    let left_blocks = blocks(&left.markdown);
+    let right_blocks = blocks(&right.markdown);
+
+    let total = |bs: &[Block]| bs.iter().map(Block::len).sum::<usize>().max(1) as f64;
+    let ratio = total(&right_blocks) / total(&left_blocks);
+
+    let left_anchors: Vec<usize> = (0..left_blocks.len())
+        .filter(|&i| left_blocks[i].is_anchor())
+        .collect();
+    let right_anchors: Vec<usize> = (0..right_blocks.len())
+        .filter(|&i| right_blocks[i].is_anchor())
+        .collect();
+    let matched = match_anchors(&left_anchors, &right_anchors, &left_blocks, &right_blocks);
+
+    let mut pairs = Vec::new();
+    let (mut li, mut ri) = (0, 0);
+    for (la, ra) in matched
+        .into_iter()
+        .chain(std::iter::once((left_blocks.len(), right_blocks.len())))
+    {
+        align_segment(
+            &left_blocks[li..la],
+            &right_blocks[ri..ra],
+            ratio,
+            &mut pairs,
+        );
+        if la < left_blocks.len() && ra < right_blocks.len() {
+            pairs.push(pair(&[&left_blocks[la]], &[&right_blocks[ra]]));
+        }
+        li = la + 1;
+        ri = ra + 1;
+    }
+    pairs
+}
+
+/// Longest common subsequence of anchors by kind; returns matched
+/// `(left index, right index)` block positions in order.
+fn match_anchors(
+    left: &[usize],
+    right: &[usize],
+    left_blocks: &[Block],
+    right_blocks: &[Block],
+) -> Vec<(usize, usize)> {
+    let (n, m) = (left.len(), right.len());
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if left_blocks[left[i]].kind == right_blocks[right[j]].kind {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut matched = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if left_blocks[left[i]].kind == right_blocks[right[j]].kind {
+            matched.push((left[i], right[j]));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matched
+}
+
+/// Align the blocks between two anchors with a length-based dynamic program.
+/// Unmatched anchors inside the segment are kept as one-sided pairs.
+fn align_segment(left: &[Block], right: &[Block], ratio: f64, pairs: &mut Vec<AlignedPair>) {
+    let (n, m) = (left.len(), right.len());
+    let mut cost = vec![vec![f64::INFINITY; m + 1]; n + 1];
+    let mut step = vec![vec![(0usize, 0usize); m + 1]; n + 1];
+    cost[0][0] = 0.0;
+
+    for i in 0..=n {
+        for j in 0..=m {
+            if i == 0 && j == 0 {
+                continue;
+            }
+            for (di, dj) in [(1, 1), (1, 0), (0, 1), (2, 1), (1, 2)] {
+                if di > i || dj > j {
+                    continue;
+                }
+                let ls = &left[i - di..i];
+                let rs = &right[j - dj..j];
+                let c = cost[i - di][j - dj] + match_cost(ls, rs, ratio);
+                if c < cost[i][j] {
+                    cost[i][j] = c;
+                    step[i][j] = (di, dj);
+                }
+            }
+        }
+    }
+
+    let mut segment = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let (di, dj) = step[i][j];
+        let ls: Vec<&Block> = left[i - di..i].iter().collect();
+        let rs: Vec<&Block> = right[j - dj..j].iter().collect();
+        segment.push(pair(&ls, &rs));
+        i -= di;
+        j -= dj;
+    }
+    segment.reverse();
+    pairs.extend(segment);
+}
+
+fn match_cost(left: &[Block], right: &[Block], ratio: f64) -> f64 {
+    if left.is_empty() || right.is_empty() {
+        return SKIP_PENALTY;
+    }
+    // Anchors only pair with anchors of the same kind, and never merge.
+    let anchors = left.iter().chain(right).filter(|b| b.is_anchor()).count();
+    if anchors > 0 {
+        let same = left.len() == 1 && right.len() == 1 && left[0].kind == right[0].kind;
+        return if same { 0.0 } else { f64::INFINITY };
+    }
+
+    let ll: usize = left.iter().map(Block::len).sum();
+    let rl: usize = right.iter().map(Block::len).sum();
+    let expected = ll as f64 * ratio;
+    let delta = (rl as f64 - expected) / (ll as f64 * LENGTH_VARIANCE).sqrt().max(1.0);
+    let prior = if left.len() == 1 && right.len() == 1 {
+        0.0
+    } else {
+        MERGE_PENALTY
+    };
+    delta.abs() + prior
+}
+
+fn pair(left: &[&Block], right: &[&Block]) -> AlignedPair {
+    let kind = match left.first().or(right.first()).map(|b| b.kind) {
+        Some(BlockKind::Heading(_)) => "heading",
+        Some(BlockKind::Image) => "image",
+        _ => "paragraph",
+    };
+    let texts = |bs: &[&Block]| bs.iter().flat_map(|b| b.texts.iter().cloned()).collect();
+    AlignedPair {
+        kind,
+        left: texts(left),
+        right: texts(right),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{extract_from_html, ExtractOptions};
+
+    fn extract(html: &str, url: &str) -> ExtractResult {
+        extract_from_html(html, url, &ExtractOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn aligns_saved_editions() {
+        let en = extract(
+            include_str!("../tests/fixtures/align/en.html"),
+            "https://www.jw.org/en/bible-teachings/teenagers/ask/make-friends/",
+        );
+        let es = extract(
+            include_str!("../tests/fixtures/align/es.html"),
+            "https://www.jw.org/es/ensenanzas-biblicas/adolescentes/preguntas/hacer-amigos/",
+        );
+        let pairs = align(&en, &es);
+
+        let kinds: Vec<&str> = pairs.iter().map(|p| p.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                "heading",
+                "paragraph",
+                "image",
+                "heading",
+                "paragraph",
+                "paragraph",
+                "heading",
+                "paragraph"
+            ]
+        );
+        assert!(pairs[0].left[0].contains("Good Friends"));
+        assert!(pairs[0].right[0].contains("buenos amigos"));
+
+        // The image keeps its caption on both sides.
+        assert_eq!(pairs[2].left.len(), 2);
+        assert!(pairs[2].right[1].contains("buen amigo"));
+
+        // One English paragraph was split in two in the Spanish edition.
+        assert_eq!(pairs[4].left.len(), 1);
+        assert_eq!(pairs[4].right.len(), 2);
+        assert!(pairs[4].right[1].starts_with("La verdadera amistad"));
+        assert!(pairs[5].right[0].starts_with("Puedes aprender"));
+    }
+
+    #[test]
+    fn keeps_unmatched_blocks() {
+        let left = blocks("# Title\n\nOne paragraph that only exists here.");
+        let right = blocks("# Título");
+        let mut pairs = Vec::new();
+        align_segment(&left[1..], &right[1..], 1.0, &mut pairs);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].left.len(), 1);
+        assert!(pairs[0].right.is_empty());
+    }
+
+    #[test]
+    fn attaches_captions_to_images() {
+        let parsed = blocks("![Alt](https://example.org/a.jpg)\n\n*Caption*\n\n*Not a caption*");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].kind, BlockKind::Image);
+        assert_eq!(parsed[0].texts.len(), 2);
+        assert_eq!(parsed[1].kind, BlockKind::Paragraph);
+    }
+}
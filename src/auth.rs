@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Header carrying the caller's API key (the one Kong's key-auth reads).
+pub const API_KEY_HEADER: &str = "apikey";
+/// How often a keys file is checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+static KEYS: Lazy<RwLock<Option<KeySet>>> = Lazy::new(|| RwLock::new(None));
+
+// ── Error type ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("cannot read API keys file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid API keys: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("key {0:?}: sha256 must be 64 hex digits")]
+    Hash(String),
+}
+
+// ── Config format ────────────────────────────────────────────────────────────
+
+/// What a key may be used for. `Batch` and `ArchiveAdmin` are accepted in
+/// keys files so they can be granted ahead of time, but no route requires
+/// them yet; only `Extract` gates anything today.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Extraction, editions, alignment and images.
+    Extract,
+    /// Reserved for batch extraction.
+    Batch,
+    /// Reserved for archive administration.
+    ArchiveAdmin,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Extract => "extract",
+            Scope::Batch => "batch",
+            Scope::ArchiveAdmin => "archive_admin",
+        }
+    }
+}
+
+/// One entry of the keys file. Only the SHA-256 of the key is stored.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyConfig {
+    /// Shown in audit logs instead of the key.
+    pub name: String,
+    /// Lowercase hex SHA-256 of the key.
+    pub sha256: String,
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeysFile {
+    pub keys: Vec<KeyConfig>,
+}
+
+// ── Key set ──────────────────────────────────────────────────────────────────
+
+/// A key that authenticated a request.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Default)]
+struct KeySet {
+    /// By hex SHA-256 of the key.
+    keys: HashMap<String, ApiKey>,
+}
+
+impl KeySet {
+    fn from_json(json: &str) -> Result<Self, AuthError> {
+        let file: KeysFile = serde_json::from_str(json)?;
+        let mut keys = HashMap::new();
+        for key in file.keys {
+            let hash = key.sha256.trim().to_lowercase();
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(AuthError::Hash(key.name));
+            }
+            if key.disabled {
+                continue;
+            }
+            keys.insert(
+                hash,
+                ApiKey {
+                    name: key.name,
+                    scopes: key.scopes,
+                },
+            );
+        }
+        Ok(KeySet { keys })
+    }
+}
+
+/// Outcome of checking a request's API key.
+pub enum Access {
+    /// Authentication is not configured.
+    Open,
+    Granted(ApiKey),
+    Missing,
+    Invalid,
+    /// The key is valid but lacks the scope.
+    Denied(ApiKey),
+}
+
+/// Check `key` (the `apikey` header) for `scope`; `None` accepts any valid
+/// key.
+pub fn authorize(key: Option<&str>, scope: Option<Scope>) -> Access {
+    check(KEYS.read().unwrap().as_ref(), key, scope)
+}
+
+/// `authorize` against `set`, or with authentication off if `None`.
+fn check(set: Option<&KeySet>, key: Option<&str>, scope: Option<Scope>) -> Access {
+    let Some(set) = set else {
+        return Access::Open;
+    };
+    let Some(key) = key.map(str::trim).filter(|k| !k.is_empty()) else {
+        return Access::Missing;
+    };
+    match set.keys.get(&hash(key)) {
+        None => Access::Invalid,
+        Some(found) if scope.is_some_and(|s| !found.scopes.contains(&s)) => {
+            Access::Denied(found.clone())
+        }
+        Some(found) => Access::Granted(found.clone()),
+    }
+}
+
+/// Lowercase hex SHA-256, as stored in the keys file.
+pub fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// ── Loading ──────────────────────────────────────────────────────────────────
+
+/// Enable authentication when `JW_NEWS_READER_API_KEYS` is set, either to a
+/// keys file or to the same JSON inline. A file is re-read whenever it
+/// changes, so keys can be added and revoked without a restart.
+pub fn init_from_env() -> Result<(), AuthError> {
+    let value = match std::env::var("JW_NEWS_READER_API_KEYS") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(()),
+    };
+    if value.trim_start().starts_with('{') {
+        let set = KeySet::from_json(&value)?;
+        tracing::info!(
+            "loaded {} API key(s) from JW_NEWS_READER_API_KEYS",
+            set.keys.len()
+        );
+        *KEYS.write().unwrap() = Some(set);
+        return Ok(());
+    }
+
+    let path = value;
+    let mut modified = load_file(&path)?;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            interval.tick().await;
+            let current = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            if current.is_none() || current == modified {
+                continue;
+            }
+            match load_file(&path) {
+                Ok(m) => modified = m,
+                // Keep the previous keys until the file is fixed.
+                Err(e) => {
+                    tracing::error!("not reloading API keys: {}", e);
+                    modified = current;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn load_file(path: &str) -> Result<Option<SystemTime>, AuthError> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let set = KeySet::from_json(&std::fs::read_to_string(path)?)?;
+    tracing::info!("loaded {} API key(s) from {}", set.keys.len(), path);
+    *KEYS.write().unwrap() = Some(set);
+    Ok(modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SHA-256 of "abc".
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn keys() -> KeySet {
+        KeySet::from_json(&format!(
+            r#"{{"keys": [
+                {{"name": "reader", "sha256": "{}", "scopes": ["extract"]}},
+                {{"name": "ops", "sha256": "{}", "scopes": ["batch"]}},
+                {{"name": "old", "sha256": "{}", "scopes": ["extract"], "disabled": true}}
+            ]}}"#,
+            ABC.to_uppercase(),
+            hash("ops-key"),
+            hash("old-key")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn hashes_as_lowercase_hex() {
+        assert_eq!(hash("abc"), ABC);
+        assert_eq!(hash("").len(), 64);
+    }
+
+    #[test]
+    fn loads_keys_and_drops_disabled_ones() {
+        let set = keys();
+        assert_eq!(set.keys.len(), 2);
+        // Hashes are matched in lowercase.
+        assert_eq!(set.keys[ABC].name, "reader");
+        assert!(!set.keys.contains_key(&hash("old-key")));
+    }
+
+    #[test]
+    fn rejects_bad_hashes_and_unknown_scopes() {
+        let file = |sha256: &str, scope: &str| {
+            format!(r#"{{"keys": [{{"name": "k", "sha256": "{sha256}", "scopes": ["{scope}"]}}]}}"#)
+        };
+        for sha256 in [&ABC[..63], &format!("{}0", ABC), &ABC.replace('a', "g"), ""] {
+            assert!(matches!(
+                KeySet::from_json(&file(sha256, "extract")),
+                Err(AuthError::Hash(name)) if name == "k"
+            ));
+        }
+        assert!(matches!(
+            KeySet::from_json(&file(ABC, "admin")),
+            Err(AuthError::Json(_))
+        ));
+        assert!(matches!(
+            KeySet::from_json(r#"{"keys": [], "extra": 1}"#),
+            Err(AuthError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn authorizes_by_key_and_scope() {
+        let set = keys();
+        let extract = Some(Scope::Extract);
+        assert!(matches!(check(None, None, extract), Access::Open));
+        assert!(matches!(check(Some(&set), None, extract), Access::Missing));
+        assert!(matches!(
+            check(Some(&set), Some("  "), extract),
+            Access::Missing
+        ));
+        assert!(matches!(
+            check(Some(&set), Some("nope"), extract),
+            Access::Invalid
+        ));
+        assert!(matches!(
+            check(Some(&set), Some("old-key"), extract),
+            Access::Invalid
+        ));
+        assert!(matches!(
+            check(Some(&set), Some(" abc "), extract),
+            Access::Granted(key) if key.name == "reader"
+        ));
+        assert!(matches!(
+            check(Some(&set), Some("ops-key"), extract),
+            Access::Denied(key) if key.name == "ops"
+        ));
+    }
+
+    #[test]
+    fn any_valid_key_passes_without_a_scope() {
+        let set = keys();
+        assert!(matches!(
+            check(Some(&set), Some("ops-key"), None),
+            Access::Granted(_)
+        ));
+        assert!(matches!(
+            check(Some(&set), Some("nope"), None),
+            Access::Invalid
+        ));
+        assert!(matches!(check(Some(&set), None, None), Access::Missing));
+    }
+}
@@ -0,0 +1,119 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Bytes scanned for a `<meta>` charset declaration, as in the HTML spec.
+const PRESCAN_BYTES: usize = 1024;
+
+static CONTENT_TYPE_CHARSET_RE: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r#"(?i)charset\s*=\s*["']?([A-Za-z0-9_.:\-]+)"#).unwrap());
+
+/// `<meta charset="...">` and `<meta http-equiv="Content-Type" content="...; charset=...">`.
+static META_CHARSET_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i-u)<meta\s[^>]*?charset\s*=\s*["']?\s*([A-Za-z0-9_.:\-]+)"#).unwrap()
+});
+
+// ── Decoding ─────────────────────────────────────────────────────────────────
+
+/// Decode an HTML document. The encoding is taken from a byte order mark,
+/// else the `Content-Type` charset, else a `<meta>` declaration near the top
+/// of the document; undeclared documents are read as UTF-8 when valid and as
+/// Windows-1252 otherwise. Malformed sequences become U+FFFD.
+pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| content_type.and_then(content_type_charset))
+        .or_else(|| meta_charset(bytes))
+        .unwrap_or_else(|| {
+            if std::str::from_utf8(bytes).is_ok() {
+                UTF_8
+            } else {
+                WINDOWS_1252
+            }
+        });
+    // `decode` strips a BOM and lets it override `encoding`.
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+fn content_type_charset(content_type: &str) -> Option<&'static Encoding> {
+    let cap = CONTENT_TYPE_CHARSET_RE.captures(content_type)?;
+    Encoding::for_label(cap[1].as_bytes())
+}
+
+fn meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(PRESCAN_BYTES)];
+    let cap = META_CHARSET_RE.captures(head)?;
+    let encoding = Encoding::for_label(&cap[1])?;
+    // A document that could be read far enough to find the declaration is
+    // not UTF-16, whatever it claims (HTML spec, "prescan").
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        return Some(UTF_8);
+    }
+    Some(encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bom_wins_and_is_stripped() {
+        assert_eq!(
+            decode_html(
+                b"\xEF\xBB\xBFcaf\xC3\xA9",
+                Some("text/html; charset=iso-8859-1")
+            ),
+            "café"
+        );
+        assert_eq!(decode_html(b"\xFF\xFEh\x00i\x00", None), "hi");
+    }
+
+    #[test]
+    fn content_type_charset_beats_meta() {
+        let html = b"<meta charset=\"utf-8\"><p>caf\xE9</p>";
+        assert_eq!(
+            decode_html(html, Some("text/html; charset=\"ISO-8859-1\"")),
+            "<meta charset=\"utf-8\"><p>café</p>"
+        );
+    }
+
+    #[test]
+    fn unknown_content_type_charset_falls_back_to_meta() {
+        let html = b"<meta charset=windows-1252><p>\x93hi\x94</p>";
+        assert_eq!(
+            decode_html(html, Some("text/html; charset=bogus")),
+            "<meta charset=windows-1252><p>\u{201C}hi\u{201D}</p>"
+        );
+    }
+
+    #[test]
+    fn reads_http_equiv_meta() {
+        let html =
+            b"<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=Shift_JIS\">\x93\xFA\x96\x7B";
+        assert!(decode_html(html, Some("text/html")).ends_with("日本"));
+    }
+
+    #[test]
+    fn ignores_meta_after_prescan() {
+        let mut html = vec![b' '; PRESCAN_BYTES];
+        html.extend_from_slice(b"<meta charset=koi8-r>\xC1");
+        assert!(decode_html(&html, None).ends_with("\u{C1}"));
+    }
+
+    #[test]
+    fn meta_utf16_means_utf8() {
+        assert_eq!(
+            decode_html(b"<meta charset=utf-16>caf\xC3\xA9", None),
+            "<meta charset=utf-16>café"
+        );
+    }
+
+    #[test]
+    fn undeclared_is_utf8_when_valid_else_windows_1252() {
+        assert_eq!(decode_html("café".as_bytes(), None), "café");
+        assert_eq!(decode_html(b"caf\xE9 \x80", None), "café €");
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap};
+
+use scraper::{Html, Selector};
+use tokio::task::JoinSet;
+use url::Url;
+
+use crate::extract::{self, ExtractOptions, ExtractionError};
+use crate::lang;
+use crate::models::Edition;
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Upper bound on editions extracted in one request.
+pub const MAX_EDITIONS: usize = 10;
+
+// ── Discovery ────────────────────────────────────────────────────────────────
+
+/// An alternate-language link found on an article page.
+#[derive(Debug, Clone)]
+pub struct AlternateLink {
+    pub language: String,
+    pub url: String,
+    pub language_name: Option<String>,
+}
+
+/// Collect translations advertised by `<link rel="alternate" hreflang>` and by
+/// `hreflang` anchors in the language picker. Only jw.org URLs are kept, and
+/// the first link per language wins.
+pub fn discover(html: &str, base_url: &Url) -> Vec<AlternateLink> {
+    let document = Html::parse_document(html);
+    let sel =
+        Selector::parse("link[rel~=\"alternate\"][hreflang][href], a[hreflang][href]").unwrap();
+
+    let mut links: Vec<AlternateLink> = Vec::new();
+    for el in document.select(&sel) {
+        let code = el.value().attr("hreflang").unwrap_or("").trim();
+        if code.is_empty() || code.eq_ignore_ascii_case("x-default") || !lang::is_valid_code(code) {
+            continue;
+        }
+        let Some(url) = el.value().attr("href").and_then(|h| base_url.join(h).ok()) else {
+            continue;
+        };
+        if extract::validate_url(url.as_str()).is_err() {
+            continue;
+        }
+
+        let language = lang::resolve(Some(code), &url).code;
+        if links.iter().any(|l| l.language == language) {
+            continue;
+        }
+
+        let language_name = el
+            .value()
+            .attr("title")
+            .map(|t| t.to_string())
+            .or_else(|| Some(extract::normalize_text(extract::collect_text(el))))
+            .filter(|t| !t.is_empty());
+        links.push(AlternateLink {
+            language,
+            url: url.to_string(),
+            language_name,
+        });
+    }
+    links
+}
+
+// ── Extraction ───────────────────────────────────────────────────────────────
+
+/// Discover the editions of `url` and extract the ones listed in `languages`
+/// (matched case-insensitively). Failures of individual editions are reported
+/// per edition rather than failing the whole request.
+pub async fn extract_editions(
+    url: &str,
+    languages: &[String],
+    options: &ExtractOptions,
+) -> Result<BTreeMap<String, Edition>, ExtractionError> {
+    if languages.len() > MAX_EDITIONS {
+        return Err(ExtractionError::InvalidRequest(format!(
+            "At most {} editions can be extracted at once",
+            MAX_EDITIONS
+        )));
+    }
+    if let Some(code) = languages.iter().find(|c| !lang::is_valid_code(c)) {
+        return Err(ExtractionError::InvalidLanguage(code.clone()));
+    }
+
+    let parsed = extract::validate_url(url)?;
+    let source_lang = lang::resolve(options.language.as_deref(), &parsed);
+    let html = extract::fetch_html(url, &source_lang.accept_language()).await?;
+
+    let mut editions: BTreeMap<String, Edition> = discover(&html, &parsed)
+        .into_iter()
+        .map(|link| {
+            (
+                link.language,
+                Edition {
+                    url: Some(link.url),
+                    language_name: link.language_name,
+                    title: None,
+                    article: None,
+                    error: None,
+                },
+            )
+        })
+        .collect();
+    // The page itself is an edition even if it does not list its own
+    // hreflang, and its title is known without another fetch.
+    let source_options = ExtractOptions {
+        language: Some(source_lang.code.clone()),
+        strict: false,
+        ..options.clone()
+    };
+    let source_title = extract::extract_from_html(&html, url, &source_options)
+        .ok()
+        .and_then(|result| result.title);
+    let source = editions
+        .entry(source_lang.code.clone())
+        .or_insert_with(|| Edition {
+            url: Some(url.to_string()),
+            language_name: None,
+            title: None,
+            article: None,
+            error: None,
+        });
+    source.title = source_title;
+
+    let mut tasks = JoinSet::new();
+    let mut task_codes = HashMap::new();
+    for requested in languages {
+        let Some((code, edition)) = editions
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(requested))
+        else {
+            editions.insert(
+                requested.clone(),
+                Edition {
+                    url: None,
+                    language_name: None,
+                    title: None,
+                    article: None,
+                    error: Some("No edition found for this language".to_string()),
+                },
+            );
+            continue;
+        };
+        let code = code.clone();
+        let edition_url = edition.url.clone().unwrap_or_default();
+        let edition_options = ExtractOptions {
+            language: Some(code.clone()),
+            ..options.clone()
+        };
+        let handle = tasks
+            .spawn(async move { extract::extract_article(&edition_url, &edition_options).await });
+        task_codes.insert(handle.id(), code);
+    }
+
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (id, result) = match joined {
+            Ok((id, result)) => (id, result.map_err(|e| e.to_string())),
+            // A panicked or cancelled task still gets an answer.
+            Err(e) => (e.id(), Err(format!("Extraction task failed: {}", e))),
+        };
+        let Some(edition) = task_codes.get(&id).and_then(|code| editions.get_mut(code)) else {
+            continue;
+        };
+        match result {
+            Ok(article) => {
+                edition.title = article.title.clone();
+                edition.article = Some(article.into_response());
+            }
+            Err(e) => edition.error = Some(e),
+        }
+    }
+
+    Ok(editions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_alternate_links() {
+        let base = Url::parse("https://www.jw.org/en/bible-teachings/teenagers/ask/make-friends/")
+            .unwrap();
+        let links = discover(
+            include_str!("../tests/fixtures/editions/hreflang.html"),
+            &base,
+        );
+        let found: Vec<(&str, &str, Option<&str>)> = links
+            .iter()
+            .map(|l| {
+                (
+                    l.language.as_str(),
+                    l.url.as_str(),
+                    l.language_name.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "en",
+                    "https://www.jw.org/en/bible-teachings/teenagers/ask/make-friends/",
+                    None
+                ),
+                (
+                    "es",
+                    "https://www.jw.org/es/ensenanzas-biblicas/adolescentes/preguntas/hacer-amigos/",
+                    None
+                ),
+                (
+                    "pt-BR",
+                    "https://www.jw.org/pt/ensinos-biblicos/adolescentes/perguntas/fazer-amigos/",
+                    None
+                ),
+                (
+                    "de",
+                    "https://www.jw.org/de/biblische-lehren/jugendliche/junge-leute-fragen/freunde-finden/",
+                    Some("Deutsch")
+                ),
+                (
+                    "it",
+                    "https://www.jw.org/it/insegnamenti-biblici/giovani/i-giovani-chiedono/fare-amicizia/",
+                    Some("Italiano")
+                ),
+            ]
+        );
+    }
+}
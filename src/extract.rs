@@ -1,32 +1,49 @@
+use std::cell::RefCell;
+
 use once_cell::sync::Lazy;
 use regex::Regex;
 use scraper::{ElementRef, Html, Selector};
 use serde_json::Value;
 use url::Url;
 
-use crate::models::ImageInfo;
+use crate::models::{
+    ContainerStrategy, ExtractDebug, ExtractExplain, ExtractQuality, ExtractResponse,
+    FallbackImageDebug, ImageInfo, ImageVariant, MediaInfo, QualityStatus, SkipReason,
+    SkippedElement, WolData,
+};
+use crate::lang::{self, Language};
+use crate::rules::{self, Profile, SiteRules};
+use crate::{
+    charset, images, media, net, politeness, quality, robots, scoring, subtitles, upstream, wol,
+};
 
 // ── Constants ────────────────────────────────────────────────────────────────
 
 const USER_AGENT: &str = "jw-news-reader-api/1.0";
 const MIN_TEXT_LEN: usize = 200;
 const CONTROL_NEEDLES: &[&str] = &["play", "audio", "video"];
+/// Hosts (and their subdomains) articles may be fetched from.
+const ARTICLE_HOSTS: &[&str] = &["jw.org"];
+/// Redirects followed per request.
+const MAX_REDIRECTS: usize = 10;
+/// Default for `JW_NEWS_READER_MAX_BODY_BYTES`.
+const DEFAULT_MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+/// Documents with more nodes or deeper nesting are not walked.
+const MAX_DOM_NODES: usize = 250_000;
+const MAX_DOM_DEPTH: usize = 256;
+/// Hosts (and their subdomains) that serve jw.org article assets.
+const ASSET_HOSTS: &[&str] = &["jw.org", "jw-cdn.org"];
+const IMAGE_ACCEPT: &str = "image/avif,image/webp,image/*;q=0.9,*/*;q=0.5";
+/// CDN hosts that serve article images (see `CMS_IMAGE_RE` and `AKAMAI_IMAGE_RE`).
+const IMAGE_HOSTS: &[&str] = &["jw-cdn.org", "assetsnffrgf-a.akamaihd.net"];
+/// Private-use characters the `pre` handler puts on its opening and closing
+/// fence lines, so `tidy_lines` knows which lines are code without guessing
+/// from their text.
+const FENCE_OPEN: char = '\u{E000}';
+const FENCE_CLOSE: char = '\u{E001}';
 
 // ── Lazy static regexes ──────────────────────────────────────────────────────
 
-static KEYWORD_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(?i)(article|content|pub|body)").unwrap());
-
-static PLAYER_CLASS_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(?i)(player|audio|video|jwplayer|vjs|media|play)").unwrap());
-
-static METADATA_CLASS_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?i)(publication|issue|magazine|context|related|footer|language|promo|share)")
-        .unwrap()
-});
-
-static ISSUE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\bwp\d{2}\b").unwrap());
-
 static CMS_IMAGE_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"(?i)https?://cms-imgp\.jw-cdn\.org/img/p/[^\s"'<>]+"#).unwrap()
 });
@@ -38,7 +55,13 @@ static AKAMAI_IMAGE_RE: Lazy<Regex> = Lazy::new(|| {
 static IMAGE_SIZE_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?i)_(xs|s|m|l|xl)(?:\b|\.|_)").unwrap());
 
-static MULTI_NEWLINE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n{3,}").unwrap());
+/// Largest (decompressed) response body read from upstream.
+static MAX_BODY_BYTES: Lazy<usize> = Lazy::new(|| {
+    std::env::var("JW_NEWS_READER_MAX_BODY_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_BODY_BYTES)
+});
 
 // ── Error type ───────────────────────────────────────────────────────────────
 
@@ -46,12 +69,148 @@ static MULTI_NEWLINE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n{3,}").unwrap
 pub enum ExtractionError {
     #[error("{0}")]
     InvalidUrl(String),
+    #[error("Invalid language code: {0}")]
+    InvalidLanguage(String),
+    #[error("{0}")]
+    InvalidRequest(String),
     #[error("URL did not return HTML")]
     NotHtml,
-    #[error("Upstream returned an error")]
-    Upstream,
-    #[error("{0}")]
+    #[error("URL did not return an image")]
+    NotImage,
+    #[error("Redirect to a disallowed URL: {0}")]
+    RedirectNotAllowed(String),
+    #[error("Too many redirects (more than {0})")]
+    TooManyRedirects(usize),
+    #[error("Host does not resolve to a public address: {0}")]
+    BlockedAddress(String),
+    #[error("Upstream response exceeds {0} bytes")]
+    ResponseTooLarge(usize),
+    #[error("Document too large to extract: {0}")]
+    DocumentTooLarge(String),
+    #[error("Upstream {host} is unavailable; retry in {retry_after}s")]
+    CircuitOpen { host: String, retry_after: u64 },
+    #[error("Too many requests queued for {host}; retry in {retry_after}s")]
+    OutboundRateLimited { host: String, retry_after: u64 },
+    #[error("Blocked by robots.txt: {0}")]
+    DisallowedByRobots(String),
+    #[error("Upstream request timed out: {0}")]
+    Timeout(String),
+    #[error("DNS lookup failed for {0}")]
+    Dns(String),
+    #[error("TLS error: {0}")]
+    Tls(String),
+    #[error("Could not connect to upstream: {0}")]
+    Connect(String),
+    #[error("Upstream page not found (status {0})")]
+    UpstreamNotFound(u16),
+    #[error("Upstream server error (status {0})")]
+    UpstreamServerError(u16),
+    #[error("Upstream returned status {0}")]
+    UpstreamStatus(u16),
+    #[error("Upstream returned an empty document")]
+    EmptyContent,
+    #[error("No article content could be extracted ({0})")]
+    ExtractionFailed(String),
+    #[error("Upstream request failed: {0}")]
     Request(String),
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl ExtractionError {
+    /// Stable, machine-readable error code for API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ExtractionError::InvalidUrl(_) => "invalid_url",
+            ExtractionError::InvalidLanguage(_) => "invalid_language",
+            ExtractionError::InvalidRequest(_) => "invalid_request",
+            ExtractionError::NotHtml => "not_html",
+            ExtractionError::NotImage => "not_image",
+            ExtractionError::RedirectNotAllowed(_) => "redirect_not_allowed",
+            ExtractionError::TooManyRedirects(_) => "too_many_redirects",
+            ExtractionError::BlockedAddress(_) => "blocked_address",
+            ExtractionError::ResponseTooLarge(_) => "response_too_large",
+            ExtractionError::DocumentTooLarge(_) => "document_too_large",
+            ExtractionError::CircuitOpen { .. } => "circuit_open",
+            ExtractionError::OutboundRateLimited { .. } => "upstream_rate_limited",
+            ExtractionError::DisallowedByRobots(_) => "robots_disallowed",
+            ExtractionError::Timeout(_) => "upstream_timeout",
+            ExtractionError::Dns(_) => "dns_failure",
+            ExtractionError::Tls(_) => "tls_error",
+            ExtractionError::Connect(_) => "connect_failed",
+            ExtractionError::UpstreamNotFound(_) => "upstream_not_found",
+            ExtractionError::UpstreamServerError(_) => "upstream_server_error",
+            ExtractionError::UpstreamStatus(_) => "upstream_error",
+            ExtractionError::EmptyContent => "empty_content",
+            ExtractionError::ExtractionFailed(_) => "extraction_failed",
+            ExtractionError::Request(_) => "request_failed",
+            ExtractionError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Whether the same request may succeed if repeated later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ExtractionError::CircuitOpen { .. }
+            | ExtractionError::OutboundRateLimited { .. }
+            | ExtractionError::Timeout(_)
+            | ExtractionError::Dns(_)
+            | ExtractionError::Connect(_)
+            | ExtractionError::UpstreamServerError(_)
+            | ExtractionError::EmptyContent => true,
+            ExtractionError::UpstreamStatus(status) => *status == 429,
+            _ => false,
+        }
+    }
+
+    /// The HTTP status jw.org answered with, for errors caused by one.
+    pub fn upstream_status(&self) -> Option<u16> {
+        match self {
+            ExtractionError::UpstreamNotFound(status)
+            | ExtractionError::UpstreamServerError(status)
+            | ExtractionError::UpstreamStatus(status) => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Seconds the caller should wait before retrying, if known.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            ExtractionError::CircuitOpen { retry_after, .. }
+            | ExtractionError::OutboundRateLimited { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Classify a failed request by its cause.
+    fn from_reqwest(e: reqwest::Error) -> Self {
+        if let Some(blocked) = net::find_cause::<net::BlockedRedirect>(&e) {
+            ExtractionError::RedirectNotAllowed(blocked.0.clone())
+        } else if let Some(too_many) = net::find_cause::<net::TooManyRedirects>(&e) {
+            ExtractionError::TooManyRedirects(too_many.0)
+        } else if let Some(blocked) = net::find_cause::<net::BlockedAddress>(&e) {
+            ExtractionError::BlockedAddress(blocked.0.clone())
+        } else if let Some(failure) = net::find_cause::<net::DnsFailure>(&e) {
+            ExtractionError::Dns(failure.host.clone())
+        } else if let Some(tls) = net::find_cause::<rustls::Error>(&e) {
+            ExtractionError::Tls(tls.to_string())
+        } else if e.is_timeout() {
+            ExtractionError::Timeout(e.to_string())
+        } else if e.is_connect() {
+            ExtractionError::Connect(e.to_string())
+        } else {
+            ExtractionError::Request(e.to_string())
+        }
+    }
+
+    /// Classify a non-success upstream status.
+    fn from_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            code @ (404 | 410) => ExtractionError::UpstreamNotFound(code),
+            code @ 500..=599 => ExtractionError::UpstreamServerError(code),
+            code => ExtractionError::UpstreamStatus(code),
+        }
+    }
 }
 
 // ── Public result type ───────────────────────────────────────────────────────
@@ -61,6 +220,67 @@ pub struct ExtractResult {
     pub title: Option<String>,
     pub source_url: String,
     pub images: Vec<ImageInfo>,
+    pub media: Vec<MediaInfo>,
+    pub wol: Option<WolData>,
+    pub quality: ExtractQuality,
+    pub debug: ExtractDebug,
+}
+
+impl ExtractResult {
+    pub fn into_response(self) -> ExtractResponse {
+        ExtractResponse {
+            markdown: self.markdown,
+            title: self.title,
+            source_url: self.source_url,
+            images: self.images,
+            media: self.media,
+            wol: self.wol,
+            quality: self.quality,
+            debug: self.debug,
+        }
+    }
+}
+
+// ── Options ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Clone)]
+pub struct ExtractOptions {
+    pub strategy: ContainerStrategy,
+    /// Requested language; inferred from the URL when not set.
+    pub language: Option<String>,
+    /// Record why elements were skipped and where the title/images came from.
+    pub debug: bool,
+    /// Download subtitle tracks of the page's media.
+    pub subtitles: bool,
+    /// Store images locally and rewrite their URLs.
+    pub mirror_images: bool,
+    /// Fill in image dimensions, MIME type and size from the CDN.
+    pub probe_images: bool,
+    /// Attach BlurHash and thumbnail previews to images.
+    pub placeholders: bool,
+    /// Fail with `ExtractionFailed` when the quality checks fail.
+    pub strict: bool,
+}
+
+// ── Container choice ─────────────────────────────────────────────────────────
+
+/// The selected content block(s) and how they were found.
+struct ContainerChoice<'doc> {
+    elements: Vec<ElementRef<'doc>>,
+    fallback_title: Option<String>,
+    source: &'static str,
+    score: Option<f64>,
+}
+
+impl<'doc> ContainerChoice<'doc> {
+    fn single(el: Option<ElementRef<'doc>>, source: &'static str) -> Self {
+        ContainerChoice {
+            elements: el.into_iter().collect(),
+            fallback_title: None,
+            source,
+            score: None,
+        }
+    }
 }
 
 // ── Walk context ─────────────────────────────────────────────────────────────
@@ -68,19 +288,61 @@ pub struct ExtractResult {
 struct WalkCtx<'a> {
     in_article_or_main: bool,
     title: Option<&'a str>,
+    rules: &'a SiteRules,
+    lang: &'a Language,
+    /// Collects skipped elements in debug mode.
+    skipped: Option<&'a RefCell<Vec<SkippedElement>>>,
 }
 
 // ── Public API ───────────────────────────────────────────────────────────────
 
-pub async fn extract_article(url: &str) -> Result<ExtractResult, ExtractionError> {
-    validate_url(url)?;
-    let html = fetch_html(url).await?;
-    Ok(extract_from_html(&html, url))
+pub async fn extract_article(
+    url: &str,
+    options: &ExtractOptions,
+) -> Result<ExtractResult, ExtractionError> {
+    let parsed = validate_url(url)?;
+    if let Some(code) = options.language.as_deref() {
+        if !lang::is_valid_code(code) {
+            return Err(ExtractionError::InvalidLanguage(code.to_string()));
+        }
+    }
+    let language = lang::resolve(options.language.as_deref(), &parsed);
+    let html = fetch_html(url, &language.accept_language()).await?;
+    let mut result = extract_from_html(&html, url, options)?;
+    if options.subtitles {
+        subtitles::fetch_tracks(&mut result.media).await;
+    }
+    // Probe before mirroring, while `url` still points at the CDN.
+    if options.probe_images {
+        images::probe(&mut result.images).await;
+    }
+    if options.placeholders {
+        images::placeholders(&mut result.images).await;
+    }
+    if options.mirror_images {
+        images::mirror(&mut result).await;
+    }
+    Ok(result)
 }
 
 // ── URL validation ───────────────────────────────────────────────────────────
 
-fn validate_url(url: &str) -> Result<(), ExtractionError> {
+pub(crate) fn validate_url(url: &str) -> Result<Url, ExtractionError> {
+    validate_url_for(url, ARTICLE_HOSTS, "Only jw.org URLs are allowed")
+}
+
+/// Like `validate_url`, but also accepts the jw.org CDN hosts that serve
+/// subtitles and other article assets.
+pub(crate) fn validate_asset_url(url: &str) -> Result<Url, ExtractionError> {
+    validate_url_for(url, ASSET_HOSTS, "Only jw.org and jw.org CDN URLs are allowed")
+}
+
+/// Accept only the CDN hosts jw.org serves article images from.
+pub(crate) fn validate_image_url(url: &str) -> Result<Url, ExtractionError> {
+    validate_url_for(url, IMAGE_HOSTS, "Only jw.org CDN image URLs are allowed")
+}
+
+fn validate_url_for(url: &str, hosts: &[&str], message: &str) -> Result<Url, ExtractionError> {
     let parsed = Url::parse(url)
         .map_err(|_| ExtractionError::InvalidUrl("Invalid URL".to_string()))?;
     if parsed.scheme() != "https" {
@@ -88,36 +350,212 @@ fn validate_url(url: &str) -> Result<(), ExtractionError> {
             "Only https URLs are allowed".to_string(),
         ));
     }
-    let host = parsed.host_str().unwrap_or("").to_lowercase();
-    if host == "jw.org" || host.ends_with(".jw.org") {
-        return Ok(());
+    if host_in(&parsed, hosts) {
+        return Ok(parsed);
     }
-    Err(ExtractionError::InvalidUrl(
-        "Only jw.org URLs are allowed".to_string(),
-    ))
+    Err(ExtractionError::InvalidUrl(message.to_string()))
+}
+
+/// Whether the URL's host is one of `hosts` or a subdomain of one.
+fn host_in(url: &Url, hosts: &[&str]) -> bool {
+    let host = url.host_str().unwrap_or("").to_lowercase();
+    hosts
+        .iter()
+        .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
 }
 
 // ── HTTP fetch ───────────────────────────────────────────────────────────────
 
-async fn fetch_html(url: &str) -> Result<String, ExtractionError> {
+pub(crate) async fn fetch_html(url: &str, accept_language: &str) -> Result<String, ExtractionError> {
+    if let Ok(parsed) = Url::parse(url) {
+        robots::check(&parsed).await?;
+    }
+    let response = fetch(
+        url,
+        ARTICLE_HOSTS,
+        "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+        accept_language,
+        None,
+    )
+    .await?;
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+
+    if !content_type.contains("text/html") {
+        return Err(ExtractionError::NotHtml);
+    }
+
+    let body = read_body(response).await?;
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Err(ExtractionError::EmptyContent);
+    }
+    Ok(charset::decode_html(&body, Some(&content_type)))
+}
+
+/// Fetch a text asset (e.g. a WebVTT subtitle file) from jw.org or its CDN.
+pub(crate) async fn fetch_asset_text(url: &str) -> Result<String, ExtractionError> {
+    validate_asset_url(url)?;
+    let response = fetch(url, ASSET_HOSTS, "text/vtt,text/plain;q=0.9,*/*;q=0.8", "en-US,en;q=0.9", None)
+        .await?;
+    let body = read_body(response).await?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Fetch an image from the jw.org CDN, returning its bytes and MIME type.
+pub(crate) async fn fetch_image(url: &str) -> Result<(Vec<u8>, String), ExtractionError> {
+    validate_image_url(url)?;
+    let response = fetch(url, IMAGE_HOSTS, IMAGE_ACCEPT, "en-US,en;q=0.9", None).await?;
+
+    let mime_type = mime_type(&response);
+    if !mime_type.starts_with("image/") {
+        return Err(ExtractionError::NotImage);
+    }
+
+    let bytes = read_body(response).await?;
+    Ok((bytes, mime_type))
+}
+
+/// Fetch a site's robots.txt. `None` when the site answered with an error
+/// status, which robots.txt treats as "no restrictions".
+pub(crate) async fn fetch_robots(url: &str) -> Result<Option<String>, ExtractionError> {
+    validate_url(url)?;
+    let response = match fetch(url, ARTICLE_HOSTS, "text/plain,*/*;q=0.8", "en-US,en;q=0.9", None).await {
+        Ok(response) => response,
+        // robots.txt treats any 4xx answer as "no restrictions".
+        Err(e) if e.upstream_status().is_some_and(|s| (400..500).contains(&s)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let body = read_body(response).await?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Read a response body, failing as soon as more than `MAX_BODY_BYTES` have
+/// arrived. Compressed bodies are decoded while streaming, so the limit
+/// applies to the decompressed size.
+async fn read_body(mut response: Response) -> Result<Vec<u8>, ExtractionError> {
+    let limit = *MAX_BODY_BYTES;
+    if response.content_length().is_some_and(|len| len > limit as u64) {
+        return Err(ExtractionError::ResponseTooLarge(limit));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(ExtractionError::from_reqwest)?
+    {
+        if body.len() + chunk.len() > limit {
+            return Err(ExtractionError::ResponseTooLarge(limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// The first bytes of an image and what the CDN reports about the whole file.
+pub(crate) struct ImageHead {
+    pub bytes: Vec<u8>,
+    /// `Content-Type`, if the CDN sent one.
+    pub mime_type: Option<String>,
+    /// Full file size from `Content-Range` or `Content-Length`.
+    pub total_size: Option<u64>,
+}
+
+/// Fetch at most `max_bytes` of an image from the jw.org CDN with a `Range`
+/// request. Servers that ignore the range are cut off after `max_bytes`.
+pub(crate) async fn fetch_image_head(url: &str, max_bytes: usize) -> Result<ImageHead, ExtractionError> {
+    validate_image_url(url)?;
+    let range = format!("bytes=0-{}", max_bytes.saturating_sub(1));
+    let mut response = fetch(url, IMAGE_HOSTS, IMAGE_ACCEPT, "en-US,en;q=0.9", Some(&range)).await?;
+
+    let mime_type = Some(mime_type(&response)).filter(|m| !m.is_empty());
+    let total_size = if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse().ok())
+    } else {
+        response.content_length()
+    };
+
+    let mut bytes = Vec::new();
+    while bytes.len() < max_bytes {
+        match response
+            .chunk()
+            .await
+            .map_err(ExtractionError::from_reqwest)?
+        {
+            Some(chunk) => bytes.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    bytes.truncate(max_bytes);
+
+    Ok(ImageHead {
+        bytes,
+        mime_type,
+        total_size,
+    })
+}
+
+/// Lowercased MIME type of a response, without parameters.
+fn mime_type(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .unwrap_or("")
+        .trim()
+        .to_lowercase()
+}
+
+/// An upstream response holding one of its host's connection slots until it
+/// is dropped.
+type Response = politeness::Admitted<reqwest::Response>;
+
+/// Send a GET request with the service's client settings and fail on
+/// non-success statuses. Callers validate the URL against `hosts` first;
+/// every redirect is validated against the same hosts, and connections are
+/// only made to public IP addresses. Each attempt waits for the host's rate
+/// limit and a free connection slot.
+async fn fetch(
+    url: &str,
+    hosts: &'static [&'static str],
+    accept: &str,
+    accept_language: &str,
+    range: Option<&str>,
+) -> Result<Response, ExtractionError> {
     let insecure = std::env::var("JW_NEWS_READER_INSECURE_SSL").as_deref() == Ok("1");
 
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert(
         reqwest::header::ACCEPT,
-        "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+        accept
             .parse()
-            .unwrap(),
+            .map_err(|_| ExtractionError::Request(format!("invalid Accept header: {}", accept)))?,
     );
     headers.insert(
         reqwest::header::ACCEPT_LANGUAGE,
-        "en-US,en;q=0.9".parse().unwrap(),
+        accept_language
+            .parse()
+            .map_err(|_| ExtractionError::InvalidLanguage(accept_language.to_string()))?,
     );
 
     let mut builder = reqwest::ClientBuilder::new()
         .connect_timeout(std::time::Duration::from_secs(5))
         .timeout(std::time::Duration::from_secs(10))
-        .redirect(reqwest::redirect::Policy::limited(10))
+        .redirect(redirect_policy(hosts))
+        .dns_resolver(std::sync::Arc::new(net::PublicResolver))
+        // A proxy would resolve host names itself, past `PublicResolver`.
+        .no_proxy()
         .user_agent(USER_AGENT)
         .default_headers(headers);
 
@@ -129,58 +567,170 @@ async fn fetch_html(url: &str) -> Result<String, ExtractionError> {
         .build()
         .map_err(|e| ExtractionError::Request(e.to_string()))?;
 
-    let response = client.get(url).send().await.map_err(|e| {
-        if e.is_timeout() {
-            ExtractionError::Request(format!("TimeoutError: {}", e))
-        } else if e.is_connect() {
-            ExtractionError::Request(format!("ConnectError: {}", e))
-        } else {
-            ExtractionError::Request(format!("RequestError: {}", e))
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+        .unwrap_or_default();
+    let circuit = upstream::check(&host)?;
+
+    // Retry connect errors, timeouts and 429/502/503/504 with backoff; only
+    // the final outcome counts towards the host's circuit, where any 5xx or
+    // unreachable host is a failure.
+    let mut attempt = 1;
+    let (outcome, slot) = loop {
+        let slot = politeness::acquire(&host).await?;
+        let mut request = client.get(url);
+        if let Some(range) = range {
+            request = request.header(reqwest::header::RANGE, range);
+        }
+        let outcome = request.send().await;
+        // A certificate or handshake problem is neither retried nor held
+        // against the host's circuit.
+        let tls = outcome
+            .as_ref()
+            .is_err_and(|e| net::find_cause::<rustls::Error>(e).is_some());
+        let transient = match &outcome {
+            Ok(response) if upstream::is_retryable_status(response.status()) => {
+                Some(upstream::retry_after(response.headers()))
+            }
+            Err(e)
+                if !tls
+                    && (e.is_connect() || e.is_timeout())
+                    && net::find_cause::<net::BlockedAddress>(e).is_none() =>
+            {
+                Some(None)
+            }
+            _ => None,
+        };
+        let Some(retry_after) = transient else {
+            match &outcome {
+                Ok(response) => circuit.answered(response.status()),
+                // The host could not be reached. A certificate problem or an
+                // address we refused says nothing about its health, and other
+                // errors (redirects, bodies) leave the circuit as it is.
+                Err(e)
+                    if !tls
+                        && net::find_cause::<net::BlockedAddress>(e).is_none()
+                        && (e.is_connect()
+                            || e.is_timeout()
+                            || net::find_cause::<net::DnsFailure>(e).is_some()) =>
+                {
+                    circuit.failed()
+                }
+                Err(_) => {}
+            }
+            break (outcome, slot);
+        };
+        match upstream::backoff(attempt, retry_after).filter(|_| attempt < upstream::MAX_ATTEMPTS) {
+            Some(delay) => {
+                drop(slot);
+                tracing::debug!("retrying {} in {:?} (attempt {})", url, delay, attempt);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            None => {
+                circuit.failed();
+                break (outcome, slot);
+            }
         }
-    })?;
+    };
+
+    let response = outcome.map_err(ExtractionError::from_reqwest)?;
 
     if !response.status().is_success() {
-        return Err(ExtractionError::Upstream);
+        return Err(ExtractionError::from_status(response.status()));
     }
 
-    let content_type = response
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("")
-        .to_lowercase();
-
-    if !content_type.contains("text/html") {
-        return Err(ExtractionError::NotHtml);
-    }
+    Ok(slot.admit(response))
+}
 
-    response
-        .text()
-        .await
-        .map_err(|e| ExtractionError::Request(e.to_string()))
+/// Follow at most `MAX_REDIRECTS` redirects, each of which must pass the
+/// same https/host allowlist as the original URL.
+fn redirect_policy(hosts: &'static [&'static str]) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        // `previous` holds the original URL and every earlier redirect, so
+        // it has N entries on the Nth redirect.
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error(net::TooManyRedirects(MAX_REDIRECTS));
+        }
+        if validate_url_for(attempt.url().as_str(), hosts, "").is_err() {
+            let target = attempt.url().to_string();
+            return attempt.error(net::BlockedRedirect(target));
+        }
+        attempt.follow()
+    })
 }
 
 // ── Main extraction pipeline ─────────────────────────────────────────────────
 
-pub fn extract_from_html(html: &str, base_url: &str) -> ExtractResult {
+/// Extract from undecoded HTML, e.g. a saved page. The encoding comes from a
+/// BOM, the `Content-Type` charset if known, or a `<meta>` declaration.
+pub fn extract_from_bytes(
+    bytes: &[u8],
+    content_type: Option<&str>,
+    base_url: &str,
+    options: &ExtractOptions,
+) -> Result<ExtractResult, ExtractionError> {
+    extract_from_html(&charset::decode_html(bytes, content_type), base_url, options)
+}
+
+pub fn extract_from_html(
+    html: &str,
+    base_url: &str,
+    options: &ExtractOptions,
+) -> Result<ExtractResult, ExtractionError> {
+    let base = Url::parse(base_url).unwrap_or_else(|_| Url::parse("https://jw.org").unwrap());
+    let site_rules = rules::current().for_url(&base);
+    tracing::debug!("extracting {} with rule {:?}", base_url, site_rules.name);
+    extract_with_rules(html, base_url, site_rules, options)
+}
+
+/// Run the extraction pipeline with an explicit rule set instead of the one
+/// configured for the URL's host and path.
+pub fn extract_with_rules(
+    html: &str,
+    base_url: &str,
+    site_rules: &SiteRules,
+    options: &ExtractOptions,
+) -> Result<ExtractResult, ExtractionError> {
     let document = Html::parse_document(html);
+    check_dom_size(&document)?;
     let base = Url::parse(base_url).unwrap_or_else(|_| Url::parse("https://jw.org").unwrap());
 
     // Extract fallback image from the full HTML before any filtering.
     let fallback_image = extract_fallback_image(html, &document, &base);
 
-    // Find the best content container element.
-    let (container, fallback_title) = find_container(&document);
+    // Audio/video is listed separately; the walker keeps players out of the markdown.
+    let media = media::extract(&document, &base);
 
-    // Resolve title: h1 in container → <title> tag → readability title.
-    let title: Option<String> = container
-        .and_then(|c| {
+    // Find the best content container element.
+    let choice = match options.strategy {
+        ContainerStrategy::Heuristic => None,
+        ContainerStrategy::Scoring => find_scored_container(&document, site_rules),
+    }
+    .unwrap_or_else(|| find_container(&document, site_rules));
+    let container = choice.elements.first().copied();
+
+    // Resolve title: rule title selectors → h1 in container → <title> tag →
+    // readability title.
+    let (title, title_source) = match site_rules
+        .title_selectors
+        .iter()
+        .find_map(|sel| {
+            document
+                .select(sel)
+                .map(|el| normalize_text(collect_text(el)))
+                .find(|s| !s.is_empty())
+        })
+        .map(|t| (t, "rule_selector"))
+        .or_else(|| container.and_then(|c| {
             let h1_sel = Selector::parse("h1").unwrap();
             c.select(&h1_sel)
                 .next()
                 .map(|el| normalize_text(collect_text(el)))
                 .filter(|s| !s.is_empty())
-        })
+                .map(|t| (t, "h1"))
+        }))
         .or_else(|| {
             let title_sel = Selector::parse("title").unwrap();
             document
@@ -188,35 +738,51 @@ pub fn extract_from_html(html: &str, base_url: &str) -> ExtractResult {
                 .next()
                 .map(|el| collect_text(el).trim().to_string())
                 .filter(|s| !s.is_empty())
+                .map(|t| (t, "title"))
         })
-        .or(fallback_title);
+        .or_else(|| choice.fallback_title.clone().map(|t| (t, "fallback")))
+    {
+        Some((t, source)) => (Some(t), source),
+        None => (None, "none"),
+    };
 
+    let language = lang::resolve(options.language.as_deref(), &base);
+    let skipped = RefCell::new(Vec::new());
     let ctx = WalkCtx {
         in_article_or_main: true,
         title: title.as_deref(),
+        rules: site_rules,
+        lang: &language,
+        skipped: options.debug.then_some(&skipped),
     };
 
     let mut images: Vec<ImageInfo> = Vec::new();
     let mut markdown = String::new();
 
-    if let Some(container) = container {
-        markdown = walk_element(container, &base, &mut images, &ctx);
+    for container in &choice.elements {
+        markdown.push_str(&walk_element(*container, &base, &mut images, &ctx));
     }
 
-    // Collapse runs of 3+ newlines and trim.
-    let markdown = MULTI_NEWLINE_RE
-        .replace_all(&markdown, "\n\n")
-        .trim()
-        .to_string();
+    // Strip stray indentation left over from flow content, collapse runs of
+    // blank lines and trim.
+    let markdown = tidy_lines(&markdown).trim().to_string();
 
     let markdown = match title.as_deref() {
         Some(t) => ensure_markdown_title(&markdown, t),
         None => markdown,
     };
 
-    // Use fallback image if we found no images in the content.
+    let fallback_debug = fallback_image.as_ref().map(|(fb, source)| FallbackImageDebug {
+        source: source.clone(),
+        url: fb.url.clone(),
+        used: images.is_empty(),
+    });
+
+    // Use fallback image if we found no images in the content; otherwise
+    // let it contribute its alt text to the same asset in the content.
     let (images, markdown) = if images.is_empty() {
-        if let Some(mut fb) = fallback_image {
+        if let Some((mut fb, _)) = fallback_image {
+            fb.image_id = image_id(&fb.url);
             if fb.alt.is_none() {
                 fb.alt = title.clone();
             }
@@ -226,30 +792,99 @@ pub fn extract_from_html(html: &str, base_url: &str) -> ExtractResult {
             (images, markdown)
         }
     } else {
+        let mut images = images;
+        if let Some((fb, _)) = fallback_image {
+            let id = image_id(&fb.url);
+            if let Some(existing) = images.iter_mut().find(|i| i.image_id == id) {
+                merge_image(existing, fb);
+            }
+        }
         (images, markdown)
     };
 
-    ExtractResult {
+    let wol = match site_rules.profile {
+        Profile::Wol => Some(wol::extract(&choice.elements, &base)),
+        Profile::Standard => None,
+    };
+
+    let quality = quality::assess(&markdown, title.as_deref(), &document);
+    if options.strict && quality.status == QualityStatus::Failed {
+        return Err(ExtractionError::ExtractionFailed(quality.issues.join(", ")));
+    }
+
+    Ok(ExtractResult {
         markdown,
         title,
         source_url: base_url.to_string(),
         images,
+        media,
+        wol,
+        quality,
+        debug: ExtractDebug {
+            strategy: options.strategy,
+            language: language.code.clone(),
+            container_source: choice.source,
+            container_score: choice.score,
+            explain: options.debug.then(|| ExtractExplain {
+                rule: site_rules.name.clone(),
+                container_paths: choice.elements.iter().map(|el| css_path(*el)).collect(),
+                title_source,
+                skipped: skipped.into_inner(),
+                fallback_image: fallback_debug,
+            }),
+        },
+    })
+}
+
+/// Refuse documents too big or too deeply nested to walk safely; the walker
+/// and text helpers recurse once per nesting level.
+fn check_dom_size(document: &Html) -> Result<(), ExtractionError> {
+    use ego_tree::iter::Edge;
+    let (mut nodes, mut depth) = (0usize, 0usize);
+    for edge in document.tree.root().traverse() {
+        match edge {
+            Edge::Open(_) => {
+                nodes += 1;
+                depth += 1;
+                if nodes > MAX_DOM_NODES {
+                    return Err(ExtractionError::DocumentTooLarge(format!(
+                        "more than {} nodes",
+                        MAX_DOM_NODES
+                    )));
+                }
+                if depth > MAX_DOM_DEPTH {
+                    return Err(ExtractionError::DocumentTooLarge(format!(
+                        "nesting deeper than {} levels",
+                        MAX_DOM_DEPTH
+                    )));
+                }
+            }
+            Edge::Close(_) => depth -= 1,
+        }
     }
+    Ok(())
 }
 
 // ── Container selection ──────────────────────────────────────────────────────
 
-fn find_container<'doc>(document: &'doc Html) -> (Option<ElementRef<'doc>>, Option<String>) {
+fn find_container<'doc>(document: &'doc Html, site_rules: &SiteRules) -> ContainerChoice<'doc> {
+    // 0. Site rule container selectors, in order.
+    for sel in &site_rules.container_selectors {
+        if let Some(el) = document.select(sel).next() {
+            return ContainerChoice::single(Some(el), "rule_selector");
+        }
+    }
+
     // 1. Prefer <article>
     let article_sel = Selector::parse("article").unwrap();
     if let Some(el) = document.select(&article_sel).next() {
-        return (Some(el), None);
+        return ContainerChoice::single(Some(el), "article");
     }
 
     // 2. Fall back to <main>
     let main_sel = Selector::parse("main").unwrap();
     if let Some(el) = document.select(&main_sel).next() {
-        return (Some(el), None);
+        return ContainerChoice::single(Some(el), "main");
     }
 
     // 3. Best <div> with a content-like class/id and sufficient text.
@@ -262,7 +897,7 @@ fn find_container<'doc>(document: &'doc Html) -> (Option<ElementRef<'doc>>, Opti
         let classes = div.value().classes().collect::<Vec<_>>().join(" ");
         let combined = format!("{} {}", id, classes);
 
-        if !KEYWORD_RE.is_match(&combined) {
+        if !site_rules.keyword_re.is_match(&combined) {
             continue;
         }
 
@@ -274,7 +909,7 @@ fn find_container<'doc>(document: &'doc Html) -> (Option<ElementRef<'doc>>, Opti
     }
 
     if best_len >= MIN_TEXT_LEN {
-        return (best, None);
+        return ContainerChoice::single(best, "keyword_div");
     }
 
     // 4. Readability fallback: use the <body> element.
@@ -286,7 +921,33 @@ fn find_container<'doc>(document: &'doc Html) -> (Option<ElementRef<'doc>>, Opti
         .filter(|s| !s.is_empty());
 
     let body_sel = Selector::parse("body").unwrap();
-    (document.select(&body_sel).next(), fallback_title)
+    ContainerChoice {
+        fallback_title,
+        ..ContainerChoice::single(document.select(&body_sel).next(), "body")
+    }
+}
+
+/// Scoring strategy: site rule selectors still win, otherwise the
+/// highest-scoring block and its related siblings. Returns `None` when no
+/// paragraph is long enough to score, so the caller can fall back to the
+/// heuristic.
+fn find_scored_container<'doc>(
+    document: &'doc Html,
+    site_rules: &SiteRules,
+) -> Option<ContainerChoice<'doc>> {
+    for sel in &site_rules.container_selectors {
+        if let Some(el) = document.select(sel).next() {
+            return Some(ContainerChoice::single(Some(el), "rule_selector"));
+        }
+    }
+
+    let candidate = scoring::best_candidate(document, site_rules)?;
+    Some(ContainerChoice {
+        elements: candidate.elements,
+        fallback_title: None,
+        source: "scoring",
+        score: Some(candidate.score),
+    })
 }
 
 // ── DOM tree walker → Markdown ───────────────────────────────────────────────
@@ -308,24 +969,29 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
             | "source"
             | "track"
     ) {
-        return String::new();
+        return skip(el, ctx, SkipReason::HardSkipTag, None);
+    }
+
+    // Skip elements removed by the site rules.
+    if ctx.rules.remove_selectors.iter().any(|sel| sel.matches(&el)) {
+        return skip(el, ctx, SkipReason::RuleRemoveSelector, None);
     }
 
     // Skip layout / navigation chrome.
     if matches!(name, "nav" | "footer" | "aside") {
-        return String::new();
+        return skip(el, ctx, SkipReason::ChromeTag, None);
     }
 
     // Skip <header> unless we are already inside article/main.
     if name == "header" && !ctx.in_article_or_main {
-        return String::new();
+        return skip(el, ctx, SkipReason::HeaderOutsideArticle, None);
     }
 
     // Skip player-control elements by aria-label.
     if let Some(aria) = el.value().attr("aria-label") {
         let lower = aria.to_lowercase();
         if CONTROL_NEEDLES.iter().any(|n| lower.contains(n)) {
-            return String::new();
+            return skip(el, ctx, SkipReason::AriaLabelControl, Some(aria));
         }
     }
 
@@ -333,7 +999,7 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
     if let Some(title_attr) = el.value().attr("title") {
         let lower = title_attr.to_lowercase();
         if CONTROL_NEEDLES.iter().any(|n| lower.contains(n)) {
-            return String::new();
+            return skip(el, ctx, SkipReason::TitleAttrControl, Some(title_attr));
         }
     }
 
@@ -343,7 +1009,7 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
         if role_lower == "button" || role_lower == "link" {
             let text = normalize_text(collect_text(el)).to_lowercase();
             if text == "play" {
-                return String::new();
+                return skip(el, ctx, SkipReason::PlayButton, None);
             }
         }
     }
@@ -354,13 +1020,13 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
     let class_id = format!("{} {}", id, classes);
 
     // Skip elements with player-like class/id that have no image and little text.
-    if PLAYER_CLASS_RE.is_match(&class_id)
+    if ctx.rules.player_class_re.is_match(&class_id)
         && !has_descendant_of_tag(el, "img")
         && !has_descendant_of_tag(el, "picture")
     {
         let text_len = normalize_text(collect_text(el)).len();
         if text_len <= 20 {
-            return String::new();
+            return skip(el, ctx, SkipReason::PlayerClass, Some(class_id.trim()));
         }
     }
 
@@ -382,27 +1048,40 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
                 .unwrap_or(false);
 
             if !contains_title {
-                if !class_id.trim().is_empty() && METADATA_CLASS_RE.is_match(&class_id) {
-                    return String::new();
+                if !class_id.trim().is_empty() && ctx.rules.metadata_class_re.is_match(&class_id)
+                {
+                    return skip(el, ctx, SkipReason::MetadataClass, Some(class_id.trim()));
                 }
 
                 let upper = normalized.to_uppercase();
-                if upper.contains("THE WATCHTOWER") || upper.contains("AWAKE!") {
-                    return String::new();
+                let table = ctx.lang.table;
+                if let Some(name) = table
+                    .publication_names
+                    .iter()
+                    .copied()
+                    .chain(ctx.rules.publication_names.iter().map(|n| n.as_str()))
+                    .find(|n| upper.contains(&n.to_uppercase()))
+                {
+                    return skip(el, ctx, SkipReason::PublicationName, Some(name));
                 }
 
-                if ISSUE_RE.is_match(&normalized)
-                    && (normalized.contains("No.")
-                        || normalized.contains("pp.")
-                        || normalized.contains("pp "))
+                if ctx.rules.issue_re.is_match(&normalized)
+                    && table
+                        .issue_markers
+                        .iter()
+                        .copied()
+                        .chain(ctx.rules.issue_markers.iter().map(|m| m.as_str()))
+                        .any(|m| normalized.contains(m))
                 {
-                    return String::new();
+                    return skip(el, ctx, SkipReason::IssuePattern, Some(&normalized));
                 }
 
                 if let Some(t) = ctx.title {
                     let lower = normalized.to_lowercase();
-                    if lower.contains("english") && lower.contains(&t.to_lowercase()) {
-                        return String::new();
+                    if table.language_names.iter().any(|n| lower.contains(n))
+                        && lower.contains(&t.to_lowercase())
+                    {
+                        return skip(el, ctx, SkipReason::LanguageBanner, Some(&normalized));
                     }
                 }
             }
@@ -413,6 +1092,9 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
     let child_ctx = WalkCtx {
         in_article_or_main: ctx.in_article_or_main || matches!(name, "article" | "main"),
         title: ctx.title,
+        rules: ctx.rules,
+        lang: ctx.lang,
+        skipped: ctx.skipped,
     };
 
     // Tag-specific markdown rendering.
@@ -435,11 +1117,16 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
                     .attr("alt")
                     .map(|s| s.trim().to_string())
                     .filter(|s| !s.is_empty());
-                images.push(ImageInfo {
+                let image = ImageInfo {
                     url: src.clone(),
                     alt: alt.clone(),
                     caption: None,
-                });
+                    variants: image_variants(el, None, &src, base_url),
+                    ..Default::default()
+                };
+                if !push_image(images, image) {
+                    return String::new();
+                }
                 format!("![{}]({})\n\n", alt.as_deref().unwrap_or(""), src)
             } else {
                 String::new()
@@ -454,11 +1141,16 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
                         .attr("alt")
                         .map(|s| s.trim().to_string())
                         .filter(|s| !s.is_empty());
-                    images.push(ImageInfo {
+                    let image = ImageInfo {
                         url: src.clone(),
                         alt: alt.clone(),
                         caption: None,
-                    });
+                        variants: image_variants(img, Some(el), &src, base_url),
+                        ..Default::default()
+                    };
+                    if !push_image(images, image) {
+                        return String::new();
+                    }
                     return format!("![{}]({})\n\n", alt.as_deref().unwrap_or(""), src);
                 }
             }
@@ -473,13 +1165,9 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
                     .unwrap_or_else(|_| h.to_string())
             });
             let content = walk_children(el, base_url, images, &child_ctx);
-            let trimmed = content.trim().to_string();
-            if trimmed.is_empty() {
-                return String::new();
-            }
             match href {
-                Some(href) => format!("[{}]({})", trimmed, href),
-                None => trimmed,
+                Some(href) => wrap_inline(&content, "[", &format!("]({})", href)),
+                None => content,
             }
         }
 
@@ -509,46 +1197,57 @@ fn walk_element(el: ElementRef<'_>, base_url: &Url, images: &mut Vec<ImageInfo>,
 
         "strong" | "b" => {
             let content = walk_children(el, base_url, images, &child_ctx);
-            let trimmed = content.trim().to_string();
-            if trimmed.is_empty() {
-                return String::new();
-            }
-            format!("**{}**", trimmed)
+            wrap_inline(&content, "**", "**")
         }
 
         "em" | "i" => {
             let content = walk_children(el, base_url, images, &child_ctx);
-            let trimmed = content.trim().to_string();
-            if trimmed.is_empty() {
-                return String::new();
-            }
-            format!("*{}*", trimmed)
+            wrap_inline(&content, "*", "*")
         }
 
         "blockquote" => {
             let content = walk_children(el, base_url, images, &child_ctx);
             let quoted = content
+                .trim()
                 .lines()
-                .map(|l| format!("> {}", l))
+                .map(|l| format!("> {}", l.trim()))
                 .collect::<Vec<_>>()
                 .join("\n");
             format!("{}\n\n", quoted)
         }
 
+        // Preformatted text keeps its source whitespace verbatim.
         "pre" => {
-            let text = collect_text(el);
-            format!("```\n{}\n```\n\n", text)
+            let text = collect_text(el).replace([FENCE_OPEN, FENCE_CLOSE], "");
+            format!(
+                "{}```\n{}\n```{}\n\n",
+                FENCE_OPEN,
+                text.trim_end_matches('\n'),
+                FENCE_CLOSE
+            )
         }
 
         "code" => {
-            let text = collect_text(el);
-            format!("`{}`", text)
+            let text = collapse_whitespace(&collect_text(el));
+            wrap_inline(&text, "`", "`")
         }
 
         _ => walk_children(el, base_url, images, &child_ctx),
     }
 }
 
+/// Drop an element from the output, recording why in debug mode.
+fn skip(el: ElementRef<'_>, ctx: &WalkCtx, reason: SkipReason, detail: Option<&str>) -> String {
+    if let Some(skipped) = ctx.skipped {
+        skipped.borrow_mut().push(SkippedElement {
+            path: css_path(el),
+            reason,
+            detail: detail.map(|d| d.to_string()),
+        });
+    }
+    String::new()
+}
+
 fn walk_children(
     el: ElementRef<'_>,
     base_url: &Url,
@@ -560,11 +1259,11 @@ fn walk_children(
     for child in el.children() {
         match child.value() {
             Node::Text(text) => {
-                result.push_str(&*text.text);
+                push_flow(&mut result, &collapse_whitespace(&text.text));
             }
             Node::Element(_) => {
                 if let Some(child_el) = ElementRef::wrap(child) {
-                    result.push_str(&walk_element(child_el, base_url, images, ctx));
+                    push_flow(&mut result, &walk_element(child_el, base_url, images, ctx));
                 }
             }
             _ => {}
@@ -600,11 +1299,16 @@ fn handle_figure(
         .map(|fc| normalize_text(collect_text(fc)))
         .filter(|s| !s.is_empty());
 
-    images.push(ImageInfo {
+    let image = ImageInfo {
         url: src.clone(),
         alt: alt.clone(),
         caption: caption.clone(),
-    });
+        variants: image_variants(img, find_first_tag(el, "picture"), &src, base_url),
+        ..Default::default()
+    };
+    if !push_image(images, image) {
+        return String::new();
+    }
 
     let alt_str = alt.as_deref().unwrap_or("");
     let mut result = format!("![{}]({})\n\n", alt_str, src);
@@ -652,6 +1356,73 @@ fn handle_list(
 
 // ── Image helpers ────────────────────────────────────────────────────────────
 
+/// Record an image, or fold it into an earlier occurrence of the same asset.
+/// Returns false for a duplicate, which should not be rendered again.
+fn push_image(images: &mut Vec<ImageInfo>, mut image: ImageInfo) -> bool {
+    image.image_id = image_id(&image.url);
+    match images.iter_mut().find(|i| i.image_id == image.image_id) {
+        Some(existing) => {
+            merge_image(existing, image);
+            false
+        }
+        None => {
+            images.push(image);
+            true
+        }
+    }
+}
+
+/// Merge alt text, caption and candidate URLs of another occurrence of the
+/// same asset into `into`, keeping its URL.
+fn merge_image(into: &mut ImageInfo, from: ImageInfo) {
+    if into.alt.is_none() {
+        into.alt = from.alt;
+    }
+    if into.caption.is_none() {
+        into.caption = from.caption;
+    }
+
+    let mut variants = std::mem::take(&mut into.variants);
+    let urls = [into.url.as_str(), from.url.as_str()];
+    let candidates = from
+        .variants
+        .into_iter()
+        .chain(url_variants(&into.url, &urls.map(str::to_string)));
+    for variant in candidates {
+        if !variants.iter().any(|v| v.url == variant.url) {
+            variants.push(variant);
+        }
+    }
+    sort_variants(&mut variants);
+    if variants.len() < 2 {
+        variants.clear();
+    }
+    into.variants = variants;
+}
+
+/// Stable id of an image asset: a hash of its `asset_key`.
+fn image_id(url: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(asset_key(url).as_bytes()))[..16].to_string()
+}
+
+/// URL without fragment; for jw.org CDN images also without query string
+/// and size label, so every copy of one CDN image has the same key. Other
+/// hosts may address different images by query string alone.
+fn asset_key(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            if !host_in(&parsed, IMAGE_HOSTS) {
+                return parsed.to_string();
+            }
+            parsed.set_query(None);
+            strip_size_label(parsed.as_str())
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
 fn resolve_img_src(el: ElementRef<'_>, base_url: &Url) -> Option<String> {
     let v = el.value();
 
@@ -682,27 +1453,28 @@ fn resolve_img_src(el: ElementRef<'_>, base_url: &Url) -> Option<String> {
     base_url.join(&src).ok().map(|u| u.to_string())
 }
 
+/// Split a `srcset` into `(url, descriptor)` pairs, e.g. `("a.jpg", Some("640w"))`.
+fn parse_srcset(srcset: &str) -> Vec<(&str, Option<&str>)> {
+    srcset
+        .split(',')
+        .filter_map(|part| {
+            let mut pieces = part.split_whitespace();
+            Some((pieces.next()?, pieces.next()))
+        })
+        .collect()
+}
+
 fn best_src_from_srcset(srcset: &str) -> Option<String> {
     let mut candidates: Vec<(f64, usize, String)> = Vec::new();
 
-    for (index, part) in srcset.split(',').enumerate() {
-        let part = part.trim();
-        if part.is_empty() {
-            continue;
-        }
-        let pieces: Vec<&str> = part.split_whitespace().collect();
-        let url = pieces[0].to_string();
-        let score: f64 = if pieces.len() > 1 {
-            let desc = pieces[1];
-            if desc.ends_with('w') || desc.ends_with('x') {
+    for (index, (url, desc)) in parse_srcset(srcset).into_iter().enumerate() {
+        let score: f64 = match desc {
+            Some(desc) if desc.ends_with('w') || desc.ends_with('x') => {
                 desc[..desc.len() - 1].parse().unwrap_or(0.0)
-            } else {
-                0.0
             }
-        } else {
-            0.0
+            _ => 0.0,
         };
-        candidates.push((score, index, url));
+        candidates.push((score, index, url.to_string()));
     }
 
     candidates.sort_by(|a, b| {
@@ -713,18 +1485,151 @@ fn best_src_from_srcset(srcset: &str) -> Option<String> {
     candidates.last().map(|(_, _, url)| url.clone())
 }
 
-fn score_image_url(url: &str) -> i32 {
+/// All candidate URLs for an image: `srcset`/`data-srcset` of the `img` and
+/// of any `<source>` in its `<picture>`, the `data-smallest`…`data-largest`
+/// attributes, and the chosen `src`. Returns nothing when `src` is the only
+/// candidate.
+fn image_variants(
+    img: ElementRef<'_>,
+    picture: Option<ElementRef<'_>>,
+    src: &str,
+    base_url: &Url,
+) -> Vec<ImageVariant> {
+    let mut variants: Vec<ImageVariant> = Vec::new();
+    let mut add = |url: &str, desc: Option<&str>, size: Option<&str>, mime_type: Option<&str>| {
+        let Ok(url) = base_url.join(url) else {
+            return;
+        };
+        let variant = ImageVariant {
+            width: desc
+                .and_then(|d| d.strip_suffix('w'))
+                .and_then(|w| w.parse().ok()),
+            density: desc
+                .and_then(|d| d.strip_suffix('x'))
+                .and_then(|x| x.parse().ok()),
+            size: size
+                .map(|s| s.to_string())
+                .or_else(|| image_size_label(url.as_str())),
+            mime_type: mime_type.map(|m| m.to_string()),
+            url: url.to_string(),
+        };
+        match variants.iter_mut().find(|v| v.url == variant.url) {
+            Some(existing) => {
+                existing.width = existing.width.or(variant.width);
+                existing.density = existing.density.or(variant.density);
+                existing.size = existing.size.take().or(variant.size);
+                existing.mime_type = existing.mime_type.take().or(variant.mime_type);
+            }
+            None => variants.push(variant),
+        }
+    };
+
+    let sources = picture
+        .into_iter()
+        .flat_map(|p| p.children().filter_map(ElementRef::wrap))
+        .filter(|c| c.value().name() == "source");
+    for source in sources {
+        let v = source.value();
+        if let Some(srcset) = v.attr("srcset").or_else(|| v.attr("data-srcset")) {
+            for (url, desc) in parse_srcset(srcset) {
+                add(url, desc, None, v.attr("type"));
+            }
+        }
+    }
+
+    let v = img.value();
+    for srcset in [v.attr("srcset"), v.attr("data-srcset")].into_iter().flatten() {
+        for (url, desc) in parse_srcset(srcset) {
+            add(url, desc, None, None);
+        }
+    }
+    for (attr, size) in [
+        ("data-smallest", "xs"),
+        ("data-small", "s"),
+        ("data-medium", "m"),
+        ("data-large", "l"),
+        ("data-largest", "xl"),
+    ] {
+        if let Some(url) = v.attr(attr) {
+            add(url, None, Some(size), None);
+        }
+    }
+    add(src, None, None, None);
+
+    sort_variants(&mut variants);
+    if variants.len() < 2 {
+        return Vec::new();
+    }
+    variants
+}
+
+/// Variants of `best` among CDN URLs found in the page source: the URLs
+/// that differ from it only in their `_xs`…`_xl` size label or query string.
+fn url_variants(best: &str, urls: &[String]) -> Vec<ImageVariant> {
+    let key = asset_key(best);
+    let mut variants: Vec<ImageVariant> = Vec::new();
+    for url in urls {
+        if asset_key(url) != key || variants.iter().any(|v| &v.url == url) {
+            continue;
+        }
+        variants.push(ImageVariant {
+            url: url.clone(),
+            width: None,
+            density: None,
+            size: image_size_label(url),
+            mime_type: None,
+        });
+    }
+    sort_variants(&mut variants);
+    if variants.len() < 2 {
+        return Vec::new();
+    }
+    variants
+}
+
+/// Smallest first: by size label, then `w` descriptor, then density.
+fn sort_variants(variants: &mut [ImageVariant]) {
+    variants.sort_by(|a, b| {
+        size_rank(a.size.as_deref())
+            .cmp(&size_rank(b.size.as_deref()))
+            .then(a.width.unwrap_or(0).cmp(&b.width.unwrap_or(0)))
+            .then(
+                a.density
+                    .unwrap_or(1.0)
+                    .partial_cmp(&b.density.unwrap_or(1.0))
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+    });
+}
+
+/// The `_xs`…`_xl` size in a jw.org CDN file name.
+fn image_size_label(url: &str) -> Option<String> {
+    IMAGE_SIZE_RE.captures(url).map(|cap| cap[1].to_lowercase())
+}
+
+/// A CDN URL with its size label removed (`a_xl.jpg` → `a_.jpg`), so that
+/// the sizes of one image compare equal.
+fn strip_size_label(url: &str) -> String {
     IMAGE_SIZE_RE
-        .captures(url)
-        .map(|cap| match cap[1].to_lowercase().as_str() {
-            "xs" => 1,
-            "s" => 2,
-            "m" => 3,
-            "l" => 4,
-            "xl" => 5,
-            _ => 0,
+        .replace(url, |cap: &regex::Captures| {
+            format!("_{}", &cap[0][1 + cap[1].len()..])
         })
-        .unwrap_or(0)
+        .into_owned()
+}
+
+fn size_rank(size: Option<&str>) -> i32 {
+    match size {
+        Some("xs") => 1,
+        Some("s") => 2,
+        Some("m") => 3,
+        Some("l") => 4,
+        Some("xl") => 5,
+        _ => 0,
+    }
+}
+
+fn score_image_url(url: &str) -> i32 {
+    size_rank(image_size_label(url).as_deref())
 }
 
 fn pick_best_image_url(urls: &[String]) -> Option<String> {
@@ -740,19 +1645,28 @@ fn pick_best_image_url(urls: &[String]) -> Option<String> {
 
 // ── Fallback image extraction (from full HTML / document) ────────────────────
 
-fn extract_fallback_image(html: &str, document: &Html, base_url: &Url) -> Option<ImageInfo> {
-    if let Some(url) = extract_meta_image(document) {
+/// Find a representative image outside the content, together with a label
+/// naming where it came from.
+fn extract_fallback_image(
+    html: &str,
+    document: &Html,
+    base_url: &Url,
+) -> Option<(ImageInfo, String)> {
+    if let Some((url, property)) = extract_meta_image(document) {
         let abs = base_url.join(&url).ok().map(|u| u.to_string()).unwrap_or(url);
-        return Some(ImageInfo { url: abs, alt: None, caption: None });
+        return Some((
+            ImageInfo { url: abs, alt: None, caption: None, ..Default::default() },
+            format!("meta:{}", property),
+        ));
     }
 
     if let Some(url) = extract_jsonld_image(document) {
         let abs = base_url.join(&url).ok().map(|u| u.to_string()).unwrap_or(url);
-        return Some(ImageInfo { url: abs, alt: None, caption: None });
+        return Some((ImageInfo { url: abs, alt: None, caption: None, ..Default::default() }, "json_ld".to_string()));
     }
 
     if let Some((url, alt)) = extract_image_link(document, base_url) {
-        return Some(ImageInfo { url, alt, caption: None });
+        return Some((ImageInfo { url, alt, caption: None, ..Default::default() }, "image_link".to_string()));
     }
 
     let cms: Vec<String> = CMS_IMAGE_RE
@@ -760,7 +1674,10 @@ fn extract_fallback_image(html: &str, document: &Html, base_url: &Url) -> Option
         .map(|m| m.as_str().to_string())
         .collect();
     if let Some(best) = pick_best_image_url(&cms) {
-        return Some(ImageInfo { url: best, alt: None, caption: None });
+        return Some((
+            ImageInfo { variants: url_variants(&best, &cms), url: best, alt: None, caption: None, ..Default::default() },
+            "cms_url".to_string(),
+        ));
     }
 
     let akamai: Vec<String> = AKAMAI_IMAGE_RE
@@ -768,13 +1685,17 @@ fn extract_fallback_image(html: &str, document: &Html, base_url: &Url) -> Option
         .map(|m| m.as_str().to_string())
         .collect();
     if let Some(best) = pick_best_image_url(&akamai) {
-        return Some(ImageInfo { url: best, alt: None, caption: None });
+        return Some((
+            ImageInfo { variants: url_variants(&best, &akamai), url: best, alt: None, caption: None, ..Default::default() },
+            "akamai_url".to_string(),
+        ));
     }
 
     None
 }
 
-fn extract_meta_image(document: &Html) -> Option<String> {
+/// Return the first meta image URL and the property/name it was found under.
+fn extract_meta_image(document: &Html) -> Option<(String, &'static str)> {
     let checks = [
         ("property", "og:image"),
         ("property", "og:image:secure_url"),
@@ -791,7 +1712,7 @@ fn extract_meta_image(document: &Html) -> Option<String> {
                 if let Some(content) = el.value().attr("content") {
                     let trimmed = content.trim().to_string();
                     if !trimmed.is_empty() {
-                        return Some(trimmed);
+                        return Some((trimmed, *value));
                     }
                 }
             }
@@ -865,10 +1786,10 @@ fn extract_image_link(document: &Html, base_url: &Url) -> Option<(String, Option
     let sel = Selector::parse("a").unwrap();
     for anchor in document.select(&sel) {
         let text = normalize_text(collect_text(anchor));
-        if text.starts_with("Image:") {
+        if let Some(rest) = text.strip_prefix("Image:") {
             if let Some(href) = anchor.value().attr("href") {
                 let abs = base_url.join(href).ok()?.to_string();
-                let alt_text = text["Image:".len()..].trim().to_string();
+                let alt_text = rest.trim().to_string();
                 let alt = if alt_text.is_empty() { None } else { Some(alt_text) };
                 return Some((abs, alt));
             }
@@ -899,6 +1820,37 @@ fn ensure_markdown_title(markdown: &str, title: &str) -> String {
     markdown.to_string()
 }
 
+/// Trim spaces at both ends of every line and keep at most one blank line
+/// in a row, except inside the code blocks marked by `FENCE_OPEN` and
+/// `FENCE_CLOSE`, which are kept verbatim. The markers are removed.
+fn tidy_lines(markdown: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    let mut blank = false;
+    for line in markdown.lines() {
+        if line.contains(FENCE_OPEN) {
+            in_fence = !line.contains(FENCE_CLOSE);
+            blank = false;
+            lines.push(line.replace([FENCE_OPEN, FENCE_CLOSE], "").trim_matches(' ').to_string());
+        } else if in_fence {
+            if line.contains(FENCE_CLOSE) {
+                in_fence = false;
+                lines.push(line.replace(FENCE_CLOSE, "").trim_matches(' ').to_string());
+            } else {
+                lines.push(line.to_string());
+            }
+        } else {
+            let line = line.trim_matches(' ');
+            if line.is_empty() && blank {
+                continue;
+            }
+            blank = line.is_empty();
+            lines.push(line.to_string());
+        }
+    }
+    lines.join("\n")
+}
+
 fn insert_fallback_image(markdown: &str, image: &ImageInfo) -> String {
     let alt = image.alt.as_deref().unwrap_or("");
     let image_md = format!("![{}]({})", alt, image.url);
@@ -928,12 +1880,12 @@ fn insert_fallback_image(markdown: &str, image: &ImageInfo) -> String {
 // ── DOM utility helpers ──────────────────────────────────────────────────────
 
 /// Recursively collect all text from an element and its descendants.
-fn collect_text(el: ElementRef<'_>) -> String {
+pub(crate) fn collect_text(el: ElementRef<'_>) -> String {
     use scraper::node::Node;
     let mut parts = Vec::new();
     for child in el.children() {
         match child.value() {
-            Node::Text(text) => parts.push((&*text.text).to_string()),
+            Node::Text(text) => parts.push(text.text.to_string()),
             Node::Element(_) => {
                 if let Some(child_el) = ElementRef::wrap(child) {
                     parts.push(collect_text(child_el));
@@ -946,10 +1898,56 @@ fn collect_text(el: ElementRef<'_>) -> String {
 }
 
 /// Collapse whitespace and trim — equivalent to Python's `" ".join(text.split())`.
-fn normalize_text(text: String) -> String {
+pub(crate) fn normalize_text(text: String) -> String {
     text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
+/// Collapse runs of HTML whitespace into a single space, as browsers do for
+/// flow content. Unlike `normalize_text`, leading/trailing spaces are kept so
+/// that words on either side of an inline tag stay separated.
+fn collapse_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut pending_space = false;
+    for ch in text.chars() {
+        if matches!(ch, ' ' | '\t' | '\n' | '\r' | '\x0c') {
+            pending_space = true;
+            continue;
+        }
+        if pending_space {
+            result.push(' ');
+            pending_space = false;
+        }
+        result.push(ch);
+    }
+    if pending_space {
+        result.push(' ');
+    }
+    result
+}
+
+/// Append `piece` to flow content, dropping its leading space when the
+/// output already ends in whitespace. Block handlers trim the final result.
+fn push_flow(result: &mut String, piece: &str) {
+    let piece = if result.ends_with([' ', '\n']) {
+        piece.strip_prefix(' ').unwrap_or(piece)
+    } else {
+        piece
+    };
+    result.push_str(piece);
+}
+
+/// Wrap inline content in markdown markers, moving any surrounding space
+/// outside the markers (`** bold **` is not valid emphasis).
+fn wrap_inline(content: &str, open: &str, close: &str) -> String {
+    let inner = content.trim();
+    if inner.is_empty() {
+        return if content.is_empty() { String::new() } else { " ".to_string() };
+    }
+    let lead = if content.starts_with(char::is_whitespace) { " " } else { "" };
+    let trail = if content.ends_with(char::is_whitespace) { " " } else { "" };
+    format!("{}{}{}{}{}", lead, open, inner, close, trail)
+}
+
 /// Return true if any descendant element (or the element itself) has
 /// normalized text that exactly matches `target`.
 fn element_has_exact_text(el: ElementRef<'_>, target: &str) -> bool {
@@ -970,6 +1968,43 @@ fn element_has_exact_text(el: ElementRef<'_>, target: &str) -> bool {
     false
 }
 
+/// Build a CSS selector path (`html > body > div#main.content:nth-of-type(2)`)
+/// identifying the element, for debug output.
+fn css_path(el: ElementRef<'_>) -> String {
+    let mut parts = Vec::new();
+    let mut current = Some(el);
+    while let Some(node) = current {
+        let value = node.value();
+        let mut part = value.name().to_string();
+        if let Some(id) = value.id() {
+            part.push('#');
+            part.push_str(id);
+        }
+        for class in value.classes() {
+            part.push('.');
+            part.push_str(class);
+        }
+        let same_tag: Vec<_> = node
+            .parent()
+            .map(|p| {
+                p.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|s| s.value().name() == value.name())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if same_tag.len() > 1 {
+            if let Some(pos) = same_tag.iter().position(|s| s.id() == node.id()) {
+                part.push_str(&format!(":nth-of-type({})", pos + 1));
+            }
+        }
+        parts.push(part);
+        current = node.parent().and_then(ElementRef::wrap);
+    }
+    parts.reverse();
+    parts.join(" > ")
+}
+
 /// Depth-first search for the first element with the given tag name.
 fn find_first_tag<'a>(el: ElementRef<'a>, tag: &str) -> Option<ElementRef<'a>> {
     use scraper::node::Node;
@@ -992,3 +2027,134 @@ fn find_first_tag<'a>(el: ElementRef<'a>, tag: &str) -> Option<ElementRef<'a>> {
 fn has_descendant_of_tag(el: ElementRef<'_>, tag: &str) -> bool {
     find_first_tag(el, tag).is_some()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://www.jw.org/en/library/";
+
+    /// Markdown of an article with `body` as its content.
+    fn markdown(body: &str) -> String {
+        let html = format!("<html><body><article>{}</article></body></html>", body);
+        extract_from_html(&html, URL, &ExtractOptions::default())
+            .unwrap()
+            .markdown
+    }
+
+    #[test]
+    fn collapses_indentation_and_newlines() {
+        assert_eq!(
+            markdown("<p>\n    Some   text\n    across\tlines.\n  </p>\n\n  <p>Next</p>"),
+            "Some text across lines.\n\nNext"
+        );
+    }
+
+    #[test]
+    fn moves_edge_spaces_outside_inline_markers() {
+        assert_eq!(
+            markdown(
+                "<p>A<strong> bold </strong>word and <em>it </em>more, \
+                 <a href=\"/x\"> link</a>.</p>"
+            ),
+            "A **bold** word and *it* more, [link](https://www.jw.org/x)."
+        );
+    }
+
+    #[test]
+    fn keeps_pre_verbatim() {
+        assert_eq!(
+            markdown("<pre>fn main() {\n    let x = 1;\n\n\n    x\n}\n</pre><p>  after   pre </p>"),
+            "```\nfn main() {\n    let x = 1;\n\n\n    x\n}\n```\n\nafter pre"
+        );
+    }
+
+    #[test]
+    fn fence_lines_inside_pre_do_not_confuse_tidying() {
+        assert_eq!(
+            markdown("<pre>text\n```\n  indented</pre><p>   Trailing   </p><pre>  b\n</pre>"),
+            "```\ntext\n```\n  indented\n```\n\nTrailing\n\n```\n  b\n```"
+        );
+    }
+
+    #[test]
+    fn br_breaks_lines() {
+        assert_eq!(markdown("<p>one<br>two<br/>  three</p>"), "one\ntwo\nthree");
+    }
+
+    #[test]
+    fn inline_code_collapses_whitespace() {
+        assert_eq!(
+            markdown("<p>Call <code>  foo(\n  a)  </code> now</p>"),
+            "Call `foo( a)` now"
+        );
+    }
+
+    #[test]
+    fn whitespace_helpers() {
+        assert_eq!(collapse_whitespace("\n  a \t\r\n b  "), " a b ");
+        assert_eq!(wrap_inline(" x ", "**", "**"), " **x** ");
+        assert_eq!(wrap_inline("   ", "*", "*"), " ");
+        assert_eq!(wrap_inline("", "*", "*"), "");
+
+        let mut flow = "word ".to_string();
+        push_flow(&mut flow, " next");
+        assert_eq!(flow, "word next");
+    }
+
+    #[test]
+    fn merges_cdn_image_sizes_but_not_query_addressed_images() {
+        let html = r#"<html><body><article>
+            <p>Intro text for the article.</p>
+            <figure><img src="https://cms-imgp.jw-cdn.org/img/p/1102/univ/art/1102_univ_sqr_s.jpg?v=1" alt="Sea"></figure>
+            <figure><img src="https://cms-imgp.jw-cdn.org/img/p/1102/univ/art/1102_univ_sqr_xl.jpg?v=2"></figure>
+            <figure><img src="https://www.jw.org/img?id=1" alt="One"></figure>
+            <figure><img src="https://www.jw.org/img?id=2#top" alt="Two"></figure>
+            <figure><img src="https://www.jw.org/img?id=2" alt="Two again"></figure>
+            </article></body></html>"#;
+        let result = extract_from_html(html, URL, &ExtractOptions::default()).unwrap();
+        let urls: Vec<&str> = result.images.iter().map(|i| i.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://cms-imgp.jw-cdn.org/img/p/1102/univ/art/1102_univ_sqr_s.jpg?v=1",
+                "https://www.jw.org/img?id=1",
+                "https://www.jw.org/img?id=2#top",
+            ]
+        );
+        let sizes: Vec<_> = result.images[0]
+            .variants
+            .iter()
+            .map(|v| v.size.as_deref())
+            .collect();
+        assert_eq!(sizes, [Some("s"), Some("xl")]);
+        assert_ne!(result.images[1].image_id, result.images[2].image_id);
+    }
+
+    #[test]
+    fn strict_fails_only_failed_extractions() {
+        let strict = ExtractOptions {
+            strict: true,
+            ..ExtractOptions::default()
+        };
+        let page = |body: &str| {
+            format!(
+                "<html><body><h1>Notice</h1><article><p>{}</p></article></body></html>",
+                body
+            )
+        };
+
+        let short = page("The meeting is moved to Sunday at noon.");
+        assert!(matches!(
+            extract_from_html(&short, URL, &strict),
+            Err(ExtractionError::ExtractionFailed(issues)) if issues.contains("few_words")
+        ));
+        let lenient = extract_from_html(&short, URL, &ExtractOptions::default()).unwrap();
+        assert_eq!(lenient.quality.status, QualityStatus::Failed);
+
+        // Low confidence is still returned in strict mode.
+        let longer = page(&"Be kind to one another. ".repeat(10));
+        let result = extract_from_html(&longer, URL, &strict).unwrap();
+        assert_eq!(result.quality.status, QualityStatus::LowConfidence);
+    }
+}
@@ -0,0 +1,749 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::extract::{self, ExtractResult, ExtractionError};
+use crate::models::{ImageFormat, ImageInfo, ImagePlaceholder};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// URL path under which mirrored images are served.
+pub const LOCAL_PREFIX: &str = "/images/";
+/// Images downloaded at the same time for one article.
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+/// Bytes read per image when probing; enough for JPEG EXIF headers.
+const PROBE_BYTES: usize = 64 * 1024;
+/// Time allowed for probing all images of one article.
+const PROBE_BUDGET: Duration = Duration::from_secs(5);
+/// Largest output width `GET /image` produces.
+pub const MAX_PROXY_WIDTH: u32 = 2048;
+/// Largest source image the proxy decodes, per side.
+const MAX_SOURCE_DIMENSION: u32 = 8_000;
+/// Memory one decoder may allocate; 4096×4096 RGBA fits.
+const MAX_DECODE_BYTES: u64 = 64 * 1024 * 1024;
+/// Images decoded at the same time across all requests, to bound memory.
+const MAX_CONCURRENT_DECODES: usize = 2;
+const JPEG_QUALITY: u8 = 82;
+/// Longest side of placeholder thumbnails.
+const THUMBNAIL_SIZE: u32 = 32;
+const THUMBNAIL_QUALITY: u8 = 60;
+/// BlurHash components along the x and y axes.
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+/// Placeholders kept in memory, by image URL.
+const PLACEHOLDER_CACHE_SIZE: usize = 2048;
+/// Default for `JW_NEWS_READER_IMAGE_DIR_MAX_BYTES`.
+const DEFAULT_IMAGE_DIR_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Directory mirrored images are stored in (`JW_NEWS_READER_IMAGE_DIR`).
+static IMAGE_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    std::env::var_os("JW_NEWS_READER_IMAGE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("images"))
+});
+
+/// Total size mirrored images may take up (`JW_NEWS_READER_IMAGE_DIR_MAX_BYTES`).
+/// The least recently mirrored files are deleted beyond it; `0` disables the cap.
+static IMAGE_DIR_MAX_BYTES: Lazy<u64> = Lazy::new(|| {
+    std::env::var("JW_NEWS_READER_IMAGE_DIR_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_IMAGE_DIR_MAX_BYTES)
+});
+
+/// Base URL the API is reachable under (`JW_NEWS_READER_PUBLIC_URL`), e.g.
+/// `https://api.example.org/reader` behind a path-stripping proxy. Mirrored
+/// image links are root-relative without it.
+static PUBLIC_URL: Lazy<String> = Lazy::new(|| {
+    std::env::var("JW_NEWS_READER_PUBLIC_URL")
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .unwrap_or_default()
+});
+
+/// Permits for `decode_blocking`, shared by the proxy and placeholders.
+static DECODE_PERMITS: Lazy<Arc<Semaphore>> =
+    Lazy::new(|| Arc::new(Semaphore::new(MAX_CONCURRENT_DECODES)));
+
+/// Suffix for temporary files, unique within the process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Computed placeholders by image URL, oldest first in `order`.
+static PLACEHOLDER_CACHE: Lazy<Mutex<PlaceholderCache>> =
+    Lazy::new(|| Mutex::new(PlaceholderCache::default()));
+
+#[derive(Default)]
+struct PlaceholderCache {
+    entries: HashMap<String, ImagePlaceholder>,
+    order: VecDeque<String>,
+}
+
+impl PlaceholderCache {
+    fn insert(&mut self, url: String, placeholder: ImagePlaceholder) {
+        if self.entries.insert(url.clone(), placeholder).is_none() {
+            self.order.push_back(url);
+        }
+        while self.order.len() > PLACEHOLDER_CACHE_SIZE {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// `<sha256>.<ext>` — the only file names `read` will serve.
+static FILE_NAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[0-9a-f]{64}\.(jpg|png|gif|webp|avif)$").unwrap());
+
+// ── Mirroring ────────────────────────────────────────────────────────────────
+
+/// Download every image of `result` into the content-addressed image
+/// directory and point `ImageInfo.url` and the markdown at the local copy.
+/// The CDN URL is kept in `original_url`; images that fail to download keep
+/// their CDN URL.
+pub async fn mirror(result: &mut ExtractResult) {
+    let mut urls: Vec<String> = Vec::new();
+    for image in &result.images {
+        if image.original_url.is_none() && !urls.contains(&image.url) {
+            urls.push(image.url.clone());
+        }
+    }
+
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+    let mut tasks = JoinSet::new();
+    for url in urls {
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let stored = store(&url).await;
+            (url, stored)
+        });
+    }
+
+    let mut local: HashMap<String, String> = HashMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((url, stored)) = joined else { continue };
+        match stored {
+            Ok(path) => {
+                local.insert(url, path);
+            }
+            Err(e) => tracing::warn!("could not mirror image {}: {}", url, e),
+        }
+    }
+
+    for image in &mut result.images {
+        if let Some(path) = local.get(&image.url) {
+            image.original_url = Some(std::mem::replace(&mut image.url, path.clone()));
+        }
+    }
+    for (url, path) in &local {
+        result.markdown = result
+            .markdown
+            .replace(&format!("]({})", url), &format!("]({})", path));
+    }
+}
+
+/// Download one image and store it as `<sha256>.<ext>`, returning its public
+/// URL. Identical images share one file.
+async fn store(url: &str) -> Result<String, String> {
+    let (bytes, mime_type) = extract::fetch_image(url).await.map_err(|e| e.to_string())?;
+    let ext =
+        extension(&mime_type).ok_or_else(|| format!("unsupported image type {}", mime_type))?;
+
+    let name = format!("{:x}.{}", Sha256::digest(&bytes), ext);
+    let path = IMAGE_DIR.join(&name);
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        // Mark the file as recently mirrored so pruning keeps it.
+        let _ = tokio::task::spawn_blocking(move || {
+            std::fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())
+        })
+        .await;
+    } else {
+        tokio::fs::create_dir_all(&*IMAGE_DIR)
+            .await
+            .map_err(|e| e.to_string())?;
+        // Write under a temporary name so readers never see a partial file.
+        let tmp = IMAGE_DIR.join(format!(
+            "{}.{}-{}.tmp",
+            name,
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&tmp, &bytes)
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| e.to_string())?;
+        if *IMAGE_DIR_MAX_BYTES > 0 {
+            let keep = name.clone();
+            let _ =
+                tokio::task::spawn_blocking(move || prune(&IMAGE_DIR, *IMAGE_DIR_MAX_BYTES, &keep))
+                    .await;
+        }
+    }
+    Ok(format!("{}{}{}", *PUBLIC_URL, LOCAL_PREFIX, name))
+}
+
+/// Delete the least recently modified images in `dir` until they fit in
+/// `max_bytes`, never deleting `keep`. Temporary files are left alone.
+fn prune(dir: &Path, max_bytes: u64, keep: &str) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            if !FILE_NAME_RE.is_match(name) {
+                return None;
+            }
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((modified, meta.len(), entry.path()))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return;
+    }
+    files.sort();
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path.file_name().is_some_and(|n| n == keep) {
+            continue;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+    tracing::info!("pruned mirrored images to {} bytes", total);
+}
+
+fn extension(mime_type: &str) -> Option<&'static str> {
+    let ext = match mime_type {
+        "image/jpeg" | "image/jpg" | "image/pjpeg" => "jpg",
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        _ => return None,
+    };
+    Some(ext)
+}
+
+// ── Probing ──────────────────────────────────────────────────────────────────
+
+/// Read the first bytes of every image to fill in its width, height, MIME
+/// type and byte size. Images not probed within `PROBE_BUDGET` are left
+/// unchanged.
+pub async fn probe(images: &mut [ImageInfo]) {
+    let mut urls: Vec<String> = Vec::new();
+    for image in images.iter() {
+        let url = image.original_url.as_ref().unwrap_or(&image.url);
+        if !urls.contains(url) {
+            urls.push(url.clone());
+        }
+    }
+
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+    let mut tasks = JoinSet::new();
+    for url in urls {
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let head = extract::fetch_image_head(&url, PROBE_BYTES).await;
+            (url, head)
+        });
+    }
+
+    let deadline = tokio::time::Instant::now() + PROBE_BUDGET;
+    let mut probed: HashMap<String, extract::ImageHead> = HashMap::new();
+    loop {
+        match tokio::time::timeout_at(deadline, tasks.join_next()).await {
+            Ok(Some(Ok((url, Ok(head))))) => {
+                probed.insert(url, head);
+            }
+            Ok(Some(Ok((url, Err(e))))) => tracing::warn!("could not probe image {}: {}", url, e),
+            Ok(Some(Err(_))) => {}
+            Ok(None) => break,
+            Err(_) => {
+                tracing::warn!("image probing ran out of time; {} left", tasks.len());
+                tasks.abort_all();
+                break;
+            }
+        }
+    }
+
+    for image in images.iter_mut() {
+        let url = image.original_url.as_ref().unwrap_or(&image.url);
+        let Some(head) = probed.get(url) else {
+            continue;
+        };
+        let (format, size) = sniff(&head.bytes).unzip();
+        image.mime_type = format
+            .map(|f| f.to_string())
+            .or_else(|| head.mime_type.clone());
+        if let Some(Some((width, height))) = size {
+            image.width = Some(width);
+            image.height = Some(height);
+        }
+        image.byte_size = head.total_size;
+    }
+}
+
+/// Detect the image format from its magic bytes and read the pixel size from
+/// the header. Supports JPEG, PNG, GIF, WebP and AVIF.
+fn sniff(bytes: &[u8]) -> Option<(&'static str, Option<(u32, u32)>)> {
+    let le16 = |i: usize| Some(u16::from_le_bytes(bytes.get(i..i + 2)?.try_into().ok()?) as u32);
+    let be32 = |i: usize| Some(u32::from_be_bytes(bytes.get(i..i + 4)?.try_into().ok()?));
+    let le24 = |i: usize| {
+        let b = bytes.get(i..i + 3)?;
+        Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+    };
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(("image/png", be32(16).zip(be32(20))));
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some(("image/gif", le16(6).zip(le16(8))));
+    }
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        return Some(("image/jpeg", jpeg_size(bytes)));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        let size = match bytes.get(12..16)? {
+            b"VP8 " => le16(26)
+                .zip(le16(28))
+                .map(|(w, h)| (w & 0x3fff, h & 0x3fff)),
+            b"VP8L" => bytes.get(21..25).map(|b| {
+                let bits = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                ((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1)
+            }),
+            b"VP8X" => le24(24).zip(le24(27)).map(|(w, h)| (w + 1, h + 1)),
+            _ => None,
+        };
+        return Some(("image/webp", size));
+    }
+    if bytes.get(4..8) == Some(b"ftyp") && matches!(bytes.get(8..12), Some(b"avif") | Some(b"avis"))
+    {
+        // Image spatial extents property: version/flags, width, height.
+        let size = bytes
+            .windows(4)
+            .position(|w| w == b"ispe")
+            .and_then(|i| be32(i + 8).zip(be32(i + 12)));
+        return Some(("image/avif", size));
+    }
+    None
+}
+
+/// Walk JPEG segments up to the first start-of-frame marker.
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |i: usize| Some(u16::from_be_bytes(bytes.get(i..i + 2)?.try_into().ok()?) as u32);
+    let mut i = 2;
+    while i + 1 < bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        match marker {
+            // Fill byte before a marker.
+            0xFF => i += 1,
+            // Standalone markers without a length.
+            0x01 | 0xD0..=0xD7 => i += 2,
+            // Start of frame (excluding DHT, JPG and DAC).
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = be16(i + 5)?;
+                let width = be16(i + 7)?;
+                return Some((width, height));
+            }
+            _ => i += 2 + be16(i + 2)? as usize,
+        }
+    }
+    None
+}
+
+// ── Placeholders ─────────────────────────────────────────────────────────────
+
+/// Attach a BlurHash and a tiny base64 thumbnail to every image. Results are
+/// cached by image URL, so repeated extractions only download new images.
+pub async fn placeholders(images: &mut [ImageInfo]) {
+    let mut missing: Vec<String> = Vec::new();
+    {
+        let cache = PLACEHOLDER_CACHE.lock().unwrap();
+        for image in images.iter() {
+            let url = image.original_url.as_ref().unwrap_or(&image.url);
+            if !cache.entries.contains_key(url) && !missing.contains(url) {
+                missing.push(url.clone());
+            }
+        }
+    }
+
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+    let mut tasks = JoinSet::new();
+    for url in missing {
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let placeholder = match extract::fetch_image(&url).await {
+                Ok((bytes, _)) => decode_blocking(move || placeholder(&bytes)).await,
+                Err(e) => Err(e),
+            };
+            (url, placeholder)
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((url, placeholder)) = joined else {
+            continue;
+        };
+        match placeholder {
+            Ok(placeholder) => PLACEHOLDER_CACHE.lock().unwrap().insert(url, placeholder),
+            Err(e) => tracing::warn!("could not compute placeholder for {}: {}", url, e),
+        }
+    }
+
+    let cache = PLACEHOLDER_CACHE.lock().unwrap();
+    for image in images.iter_mut() {
+        let url = image.original_url.as_ref().unwrap_or(&image.url);
+        image.placeholder = cache.entries.get(url).cloned();
+    }
+}
+
+fn placeholder(bytes: &[u8]) -> Result<ImagePlaceholder, ExtractionError> {
+    use base64::Engine;
+
+    let thumb = decode(bytes)?.0.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    let rgba = thumb.to_rgba8();
+    let (x, y) = BLURHASH_COMPONENTS;
+    let blurhash = blurhash::encode(x, y, rgba.width(), rgba.height(), rgba.as_raw())
+        .map_err(|e| ExtractionError::Internal(format!("could not compute BlurHash: {}", e)))?;
+
+    let mut jpeg = Vec::new();
+    thumb
+        .to_rgb8()
+        .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+            &mut jpeg,
+            THUMBNAIL_QUALITY,
+        ))
+        .map_err(|e| ExtractionError::Internal(format!("could not encode thumbnail: {}", e)))?;
+
+    Ok(ImagePlaceholder {
+        blurhash,
+        thumbnail: format!(
+            "data:image/jpeg;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(jpeg)
+        ),
+    })
+}
+
+// ── Proxy ────────────────────────────────────────────────────────────────────
+
+/// Fetch a jw.org CDN image and optionally shrink it to `width` and re-encode
+/// it as `format`. Returns the bytes and their MIME type; without `width`
+/// and `format` the image is passed through unchanged.
+pub async fn proxy(
+    url: &str,
+    width: Option<u32>,
+    format: Option<ImageFormat>,
+) -> Result<(Vec<u8>, String), ExtractionError> {
+    if width == Some(0) || width.is_some_and(|w| w > MAX_PROXY_WIDTH) {
+        return Err(ExtractionError::InvalidRequest(format!(
+            "w must be between 1 and {}",
+            MAX_PROXY_WIDTH
+        )));
+    }
+
+    let (bytes, _) = extract::fetch_image(url).await?;
+    if width.is_none() && format.is_none() {
+        // Pass through raster formats only, labelled by their content rather
+        // than the upstream header, so SVG or HTML is never served from here.
+        let (mime_type, _) = sniff(&bytes).ok_or(ExtractionError::NotImage)?;
+        return Ok((bytes, mime_type.to_string()));
+    }
+
+    decode_blocking(move || transform(&bytes, width, format)).await
+}
+
+/// Decode, resize and encode an image. Without an explicit `format`, PNG and
+/// WebP sources stay lossless (PNG) and everything else becomes JPEG. WebP
+/// output is lossless, so lossy sources asked for WebP get JPEG instead.
+fn transform(
+    bytes: &[u8],
+    width: Option<u32>,
+    format: Option<ImageFormat>,
+) -> Result<(Vec<u8>, String), ExtractionError> {
+    use image::codecs;
+
+    let (mut img, source_format) = decode(bytes)?;
+
+    if let Some(w) = width.filter(|w| *w < img.width()) {
+        img = img.resize(w, u32::MAX, image::imageops::FilterType::Lanczos3);
+    }
+
+    let lossy = match source_format {
+        Some(image::ImageFormat::Png) | Some(image::ImageFormat::Gif) => false,
+        Some(image::ImageFormat::WebP) => is_lossy_webp(bytes),
+        _ => true,
+    };
+    let format = match format {
+        // A lossless re-encode of a photo is several times its original size.
+        Some(ImageFormat::Webp) if lossy => ImageFormat::Jpeg,
+        Some(format) => format,
+        None => match source_format {
+            Some(image::ImageFormat::Png) | Some(image::ImageFormat::WebP) => ImageFormat::Png,
+            _ => ImageFormat::Jpeg,
+        },
+    };
+    let mut out = Vec::new();
+    let encoded = match format {
+        ImageFormat::Jpeg => {
+            img.to_rgb8()
+                .write_with_encoder(codecs::jpeg::JpegEncoder::new_with_quality(
+                    &mut out,
+                    JPEG_QUALITY,
+                ))
+        }
+        ImageFormat::Png => img.write_with_encoder(codecs::png::PngEncoder::new(&mut out)),
+        // The pure-Rust WebP encoder is lossless only.
+        ImageFormat::Webp => img
+            .to_rgba8()
+            .write_with_encoder(codecs::webp::WebPEncoder::new_lossless(&mut out)),
+    };
+    encoded.map_err(|e| ExtractionError::Internal(format!("could not encode image: {}", e)))?;
+
+    let mime_type = match format {
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Png => "image/png",
+        ImageFormat::Webp => "image/webp",
+    };
+    Ok((out, mime_type.to_string()))
+}
+
+/// Whether a WebP file stores its image as lossy VP8 data: the first image
+/// chunk is `VP8 ` rather than `VP8L`, possibly after `VP8X`/`ALPH` chunks.
+fn is_lossy_webp(bytes: &[u8]) -> bool {
+    let mut offset = 12;
+    while let Some(header) = bytes.get(offset..offset + 8) {
+        match &header[..4] {
+            b"VP8 " => return true,
+            b"VP8L" => return false,
+            _ => {}
+        }
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        offset = offset.saturating_add(8).saturating_add(size + (size & 1));
+    }
+    false
+}
+
+/// Run image work on the blocking pool once a decode permit is free. The
+/// permit is held by the blocking task, so it stays taken until the work is
+/// done even if the caller goes away.
+async fn decode_blocking<T, F>(work: F) -> Result<T, ExtractionError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ExtractionError> + Send + 'static,
+{
+    let permit = DECODE_PERMITS
+        .clone()
+        .acquire_owned()
+        .await
+        .map_err(|e| ExtractionError::Internal(e.to_string()))?;
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        work()
+    })
+    .await
+    .map_err(|e| ExtractionError::Internal(e.to_string()))?
+}
+
+/// Decode an image, refusing sources larger than `MAX_SOURCE_DIMENSION` per
+/// side or needing more than `MAX_DECODE_BYTES`.
+fn decode(
+    bytes: &[u8],
+) -> Result<(image::DynamicImage, Option<image::ImageFormat>), ExtractionError> {
+    let mut reader = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|_| ExtractionError::NotImage)?;
+    let format = reader.format();
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    reader.limits(limits);
+    let img = reader.decode().map_err(|_| ExtractionError::NotImage)?;
+    Ok((img, format))
+}
+
+// ── Serving ──────────────────────────────────────────────────────────────────
+
+/// Read a mirrored image by file name, returning its bytes and MIME type.
+pub async fn read(name: &str) -> Option<(Vec<u8>, &'static str)> {
+    let cap = FILE_NAME_RE.captures(name)?;
+    let mime_type = match &cap[1] {
+        "jpg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/avif",
+    };
+    let bytes = tokio::fs::read(IMAGE_DIR.join(name)).await.ok()?;
+    Some((bytes, mime_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHOTO_JPG: &[u8] = include_bytes!("../tests/fixtures/images/photo.jpg");
+    const LOGO_PNG: &[u8] = include_bytes!("../tests/fixtures/images/logo.png");
+    const LOGO_WEBP: &[u8] = include_bytes!("../tests/fixtures/images/logo.webp");
+    const ICON_GIF: &[u8] = include_bytes!("../tests/fixtures/images/icon.gif");
+
+    #[test]
+    fn sniffs_format_and_size() {
+        assert_eq!(sniff(PHOTO_JPG), Some(("image/jpeg", Some((64, 48)))));
+        assert_eq!(sniff(LOGO_PNG), Some(("image/png", Some((40, 30)))));
+        assert_eq!(sniff(LOGO_WEBP), Some(("image/webp", Some((40, 30)))));
+        assert_eq!(sniff(ICON_GIF), Some(("image/gif", Some((16, 12)))));
+    }
+
+    #[test]
+    fn sniffs_truncated_and_unknown_data() {
+        assert_eq!(sniff(&PHOTO_JPG[..8]), Some(("image/jpeg", None)));
+        assert_eq!(sniff(b"<!DOCTYPE html>"), None);
+        assert_eq!(sniff(&[]), None);
+    }
+
+    #[test]
+    fn transform_shrinks_and_keeps_format() {
+        let (bytes, mime_type) = transform(PHOTO_JPG, Some(32), None).unwrap();
+        assert_eq!(mime_type, "image/jpeg");
+        assert_eq!(sniff(&bytes), Some(("image/jpeg", Some((32, 24)))));
+
+        // Lossless sources stay lossless.
+        let (bytes, mime_type) = transform(LOGO_WEBP, Some(20), None).unwrap();
+        assert_eq!(mime_type, "image/png");
+        assert_eq!(sniff(&bytes), Some(("image/png", Some((20, 15)))));
+    }
+
+    #[test]
+    fn transform_never_enlarges() {
+        let (bytes, _) = transform(LOGO_PNG, Some(400), None).unwrap();
+        assert_eq!(sniff(&bytes), Some(("image/png", Some((40, 30)))));
+    }
+
+    #[test]
+    fn transform_converts_format() {
+        let (bytes, mime_type) = transform(ICON_GIF, None, Some(ImageFormat::Webp)).unwrap();
+        assert_eq!(mime_type, "image/webp");
+        assert_eq!(sniff(&bytes), Some(("image/webp", Some((16, 12)))));
+
+        let (bytes, mime_type) = transform(LOGO_PNG, None, Some(ImageFormat::Jpeg)).unwrap();
+        assert_eq!(mime_type, "image/jpeg");
+        assert_eq!(sniff(&bytes), Some(("image/jpeg", Some((40, 30)))));
+    }
+
+    #[test]
+    fn transform_keeps_lossy_sources_out_of_lossless_webp() {
+        let (bytes, mime_type) = transform(PHOTO_JPG, None, Some(ImageFormat::Webp)).unwrap();
+        assert_eq!(mime_type, "image/jpeg");
+        assert_eq!(sniff(&bytes), Some(("image/jpeg", Some((64, 48)))));
+
+        let (_, mime_type) = transform(LOGO_WEBP, None, Some(ImageFormat::Webp)).unwrap();
+        assert_eq!(mime_type, "image/webp");
+    }
+
+    #[test]
+    fn detects_lossy_webp_after_extended_chunks() {
+        let webp = |chunks: &[(&[u8; 4], usize)]| {
+            let mut bytes = b"RIFF\0\0\0\0WEBP".to_vec();
+            for (fourcc, size) in chunks {
+                bytes.extend_from_slice(*fourcc);
+                bytes.extend_from_slice(&(*size as u32).to_le_bytes());
+                bytes.resize(bytes.len() + size + (size & 1), 0);
+            }
+            bytes
+        };
+        assert!(is_lossy_webp(&webp(&[
+            (b"VP8X", 10),
+            (b"ALPH", 5),
+            (b"VP8 ", 20)
+        ])));
+        assert!(!is_lossy_webp(&webp(&[(b"VP8X", 10), (b"VP8L", 20)])));
+        assert!(!is_lossy_webp(LOGO_WEBP));
+        assert!(!is_lossy_webp(b"RIFF"));
+    }
+
+    #[test]
+    fn placeholder_has_blurhash_and_thumbnail() {
+        use base64::Engine;
+
+        let placeholder = placeholder(PHOTO_JPG).unwrap();
+        // 4×3 components: size flag, max AC, DC and 11 AC values.
+        assert_eq!(placeholder.blurhash.len(), 1 + 1 + 4 + 11 * 2);
+        let thumbnail = placeholder
+            .thumbnail
+            .strip_prefix("data:image/jpeg;base64,")
+            .unwrap();
+        let jpeg = base64::engine::general_purpose::STANDARD
+            .decode(thumbnail)
+            .unwrap();
+        assert_eq!(sniff(&jpeg), Some(("image/jpeg", Some((32, 24)))));
+    }
+
+    #[test]
+    fn transform_rejects_non_images_and_oversized_sources() {
+        assert!(matches!(
+            transform(b"<!DOCTYPE html>", Some(10), None),
+            Err(ExtractionError::NotImage)
+        ));
+
+        let mut wide = Vec::new();
+        image::GrayImage::new(MAX_SOURCE_DIMENSION + 1, 1)
+            .write_with_encoder(image::codecs::png::PngEncoder::new(&mut wide))
+            .unwrap();
+        assert!(matches!(
+            transform(&wide, Some(10), None),
+            Err(ExtractionError::NotImage)
+        ));
+    }
+
+    #[test]
+    fn prune_deletes_least_recently_mirrored_first() {
+        let dir = std::env::temp_dir().join(format!("jw-images-prune-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let name = |c: char| format!("{}.jpg", c.to_string().repeat(64));
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for (i, c) in ['a', 'b', 'c', 'd'].into_iter().enumerate() {
+            let path = dir.join(name(c));
+            std::fs::write(&path, [0u8; 100]).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(start + Duration::from_secs(i as u64))
+                .unwrap();
+        }
+        std::fs::write(dir.join("upload.tmp"), [0u8; 500]).unwrap();
+
+        // "a" is the oldest but was just written, so "b" and "c" go instead.
+        prune(&dir, 200, &name('a'));
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(left, [name('a'), name('d'), "upload.tmp".to_string()]);
+    }
+}
@@ -0,0 +1,211 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use url::Url;
+
+// ── Language tables ──────────────────────────────────────────────────────────
+
+/// Per-language strings used to recognise publication metadata blocks.
+#[derive(Debug)]
+pub struct LanguageTable {
+    /// jw.org language code as it appears in URLs (`/es/...`).
+    pub code: &'static str,
+    pub accept_language: &'static str,
+    /// Names of the language as shown in the language picker banner.
+    pub language_names: &'static [&'static str],
+    /// Magazine titles that mark a publication header block.
+    pub publication_names: &'static [&'static str],
+    /// Issue/page abbreviations that, next to an issue code, mark a citation line.
+    pub issue_markers: &'static [&'static str],
+}
+
+static TABLES: &[LanguageTable] = &[
+    LanguageTable {
+        code: "en",
+        accept_language: "en-US,en;q=0.9",
+        language_names: &["english"],
+        publication_names: &["THE WATCHTOWER", "AWAKE!"],
+        issue_markers: &["No.", "pp.", "pp "],
+    },
+    LanguageTable {
+        code: "es",
+        accept_language: "es-ES,es;q=0.9",
+        language_names: &["español"],
+        publication_names: &["LA ATALAYA", "¡DESPERTAD!"],
+        issue_markers: &["Núm.", "núm.", "págs.", "pág."],
+    },
+    LanguageTable {
+        code: "pt",
+        accept_language: "pt-BR,pt;q=0.9",
+        language_names: &["português"],
+        publication_names: &["A SENTINELA", "DESPERTAI!"],
+        issue_markers: &["N.º", "Nº", "N.o", "págs.", "pág."],
+    },
+    LanguageTable {
+        code: "fr",
+        accept_language: "fr-FR,fr;q=0.9",
+        language_names: &["français"],
+        publication_names: &["LA TOUR DE GARDE", "RÉVEILLEZ-VOUS"],
+        issue_markers: &["N°", "nº", "pp.", "p."],
+    },
+    LanguageTable {
+        code: "de",
+        accept_language: "de-DE,de;q=0.9",
+        language_names: &["deutsch"],
+        publication_names: &["DER WACHTTURM", "ERWACHET!"],
+        issue_markers: &["Nr.", "S."],
+    },
+    LanguageTable {
+        code: "it",
+        accept_language: "it-IT,it;q=0.9",
+        language_names: &["italiano"],
+        publication_names: &["LA TORRE DI GUARDIA", "SVEGLIATEVI!"],
+        issue_markers: &["N.", "pp.", "p."],
+    },
+    LanguageTable {
+        code: "ru",
+        accept_language: "ru-RU,ru;q=0.9",
+        language_names: &["русский"],
+        publication_names: &["СТОРОЖЕВАЯ БАШНЯ", "ПРОБУДИТЕСЬ!"],
+        issue_markers: &["№", "с."],
+    },
+];
+
+static LANGUAGE_CODE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z]{2,3}(-[A-Za-z0-9]{2,8})*$").unwrap());
+
+// ── Resolution ───────────────────────────────────────────────────────────────
+
+/// The language an article is requested and filtered in.
+#[derive(Debug, Clone)]
+pub struct Language {
+    /// Normalised code as requested (`pt-br` → `pt-BR`).
+    pub code: String,
+    /// Table for the primary subtag, or the English table when the language
+    /// has no entry of its own.
+    pub table: &'static LanguageTable,
+}
+
+impl Language {
+    pub fn accept_language(&self) -> String {
+        if self.code == self.table.code {
+            self.table.accept_language.to_string()
+        } else if self.primary() == self.table.code {
+            format!("{},{};q=0.9", self.code, self.table.code)
+        } else {
+            format!("{},en;q=0.5", self.code)
+        }
+    }
+
+    fn primary(&self) -> &str {
+        self.code.split('-').next().unwrap_or(&self.code)
+    }
+}
+
+pub fn is_valid_code(code: &str) -> bool {
+    LANGUAGE_CODE_RE.is_match(code)
+}
+
+/// Pick the language from an explicit code, else from the URL's leading path
+/// segment (`https://www.jw.org/es/...`), else English.
+pub fn resolve(requested: Option<&str>, url: &Url) -> Language {
+    let code = requested
+        .map(|c| c.to_string())
+        .or_else(|| {
+            url.path_segments()?
+                .next()
+                .filter(|s| is_valid_code(s))
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_else(|| "en".to_string());
+    let code = normalize_code(&code);
+
+    let primary = code.split('-').next().unwrap_or("").to_lowercase();
+    let table = TABLES
+        .iter()
+        .find(|t| t.code == primary)
+        .unwrap_or(&TABLES[0]);
+    Language { code, table }
+}
+
+/// Lowercase the primary subtag and uppercase a region subtag (`pt-br` → `pt-BR`).
+fn normalize_code(code: &str) -> String {
+    code.split('-')
+        .enumerate()
+        .map(|(idx, part)| {
+            if idx > 0 && part.len() == 2 {
+                part.to_uppercase()
+            } else {
+                part.to_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_url(requested: Option<&str>, url: &str) -> Language {
+        resolve(requested, &Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn infers_language_from_url_path() {
+        let lang = resolve_url(None, "https://www.jw.org/es/biblioteca/revistas/");
+        assert_eq!(lang.code, "es");
+        assert_eq!(lang.table.code, "es");
+
+        let lang = resolve_url(None, "https://www.jw.org/pt-br/biblioteca/");
+        assert_eq!(lang.code, "pt-BR");
+        assert_eq!(lang.table.code, "pt");
+    }
+
+    #[test]
+    fn explicit_language_beats_url() {
+        let lang = resolve_url(Some("FR"), "https://www.jw.org/es/biblioteca/");
+        assert_eq!(lang.code, "fr");
+        assert_eq!(lang.table.code, "fr");
+    }
+
+    #[test]
+    fn defaults_to_english() {
+        let lang = resolve_url(None, "https://www.jw.org/");
+        assert_eq!(lang.code, "en");
+        let lang = resolve_url(None, "https://www.jw.org/library/");
+        assert_eq!(lang.code, "en");
+    }
+
+    #[test]
+    fn normalizes_codes() {
+        assert_eq!(normalize_code("pt-br"), "pt-BR");
+        assert_eq!(normalize_code("ZH-hant-TW"), "zh-hant-TW");
+        assert_eq!(normalize_code("EN"), "en");
+    }
+
+    #[test]
+    fn unknown_languages_use_english_table() {
+        let lang = resolve_url(Some("ko"), "https://www.jw.org/ko/");
+        assert_eq!(lang.code, "ko");
+        assert_eq!(lang.table.code, "en");
+        assert!(lang.table.publication_names.contains(&"THE WATCHTOWER"));
+    }
+
+    #[test]
+    fn builds_accept_language() {
+        let header = |code: &str| resolve_url(Some(code), "https://www.jw.org/").accept_language();
+        assert_eq!(header("es"), "es-ES,es;q=0.9");
+        assert_eq!(header("pt-br"), "pt-BR,pt;q=0.9");
+        assert_eq!(header("ko"), "ko,en;q=0.5");
+    }
+
+    #[test]
+    fn validates_codes() {
+        assert!(is_valid_code("en"));
+        assert!(is_valid_code("pt-BR"));
+        assert!(is_valid_code("zh-Hant-TW"));
+        assert!(!is_valid_code("e"));
+        assert!(!is_valid_code("english"));
+        assert!(!is_valid_code("en_US"));
+    }
+}
@@ -1,15 +1,41 @@
+use std::net::SocketAddr;
+
 use axum::{
-    http::StatusCode,
+    body::Bytes,
+    extract::{ConnectInfo, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
-use serde_json::json;
 
+mod align;
+mod auth;
+mod charset;
+mod editions;
 mod extract;
+mod images;
+mod lang;
+mod media;
 mod models;
+mod net;
+mod politeness;
+mod quality;
+mod quota;
+mod robots;
+mod rules;
+mod scoring;
+mod subtitles;
+mod upstream;
+mod wol;
 
-use models::{ExtractRequest, ExtractResponse};
+use auth::{Access, ApiKey, Scope};
+use extract::ExtractionError;
+use models::{
+    AlignRequest, AlignResponse, CircuitState, EditionsRequest, EditionsResponse, ErrorResponse,
+    ExtractHtmlQuery, ExtractQuery, ExtractRequest, HealthResponse, ImageQuery,
+};
 
 #[tokio::main]
 async fn main() {
@@ -20,47 +46,317 @@ async fn main() {
         )
         .init();
 
+    rules::init_from_env().expect("failed to load extraction rules");
+    quota::init_from_env().expect("failed to load quotas");
+    auth::init_from_env().expect("failed to load API keys");
+
+    // The last layer runs first: API key, then quota, which counts the
+    // authenticated key.
+    let extract_routes = Router::new()
+        .route("/extract", post(extract_endpoint))
+        .route("/extract/html", post(extract_html_endpoint))
+        .route("/editions", post(editions_endpoint))
+        .route("/align", post(align_endpoint))
+        .route("/image", get(image_proxy))
+        .route_layer(middleware::from_fn(rate_limit))
+        .route_layer(middleware::from_fn_with_state(Some(Scope::Extract), require_key));
+
     let app = Router::new()
+        .merge(extract_routes)
+        // Mirrored images are loaded by <img> tags, which cannot send an API
+        // key; their content-hash names are only known from extractions.
+        .route("/images/:name", get(image_file))
         .route("/health", get(health))
-        .route("/extract", post(extract_endpoint));
+        .route(
+            "/usage",
+            get(usage).route_layer(middleware::from_fn_with_state(None, require_key)),
+        );
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await.unwrap();
     tracing::info!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+/// When API keys are configured, require one with `scope` (any valid key
+/// for `None`), pass the `ApiKey` on in the request extensions and write an
+/// audit log entry for every keyed request.
+async fn require_key(
+    State(scope): State<Option<Scope>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let key = request
+        .headers()
+        .get(auth::API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let (status, code, detail, name) = match auth::authorize(key, scope) {
+        Access::Open => return next.run(request).await,
+        Access::Granted(key) => {
+            let name = key.name.clone();
+            request.extensions_mut().insert(key);
+            let response = next.run(request).await;
+            tracing::info!(
+                target: "audit",
+                "{} {} {} key={} status={}",
+                peer.ip(),
+                method,
+                path,
+                name,
+                response.status().as_u16()
+            );
+            return response;
+        }
+        Access::Missing => (
+            StatusCode::UNAUTHORIZED,
+            "missing_api_key",
+            "No API key found in request".to_string(),
+            None,
+        ),
+        Access::Invalid => (
+            StatusCode::UNAUTHORIZED,
+            "invalid_api_key",
+            "Invalid API key".to_string(),
+            None,
+        ),
+        Access::Denied(key) => (
+            StatusCode::FORBIDDEN,
+            "insufficient_scope",
+            format!(
+                "API key lacks the {} scope",
+                scope.map(Scope::as_str).unwrap_or_default()
+            ),
+            Some(key.name),
+        ),
+    };
+    tracing::warn!(
+        target: "audit",
+        "{} {} {} key={} rejected: {}",
+        peer.ip(),
+        method,
+        path,
+        name.as_deref().unwrap_or("-"),
+        detail
+    );
+    api_error(status, code, detail, false)
+}
+
+/// Count the request against the caller's quota, keyed on the `ApiKey` left
+/// by `require_key` when there is one; callers over a limit get `429` with
+/// `Retry-After`.
+async fn rate_limit(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = request.extensions().get::<ApiKey>();
+    let client = quota::client_id(key, request.headers(), peer.ip());
+    if let Err(retry_after) = quota::check(&client) {
+        tracing::debug!("rate limited {}", client);
+        let detail = format!("Rate limit exceeded; retry in {}s", retry_after);
+        let mut response = api_error(StatusCode::TOO_MANY_REQUESTS, "rate_limited", detail, true);
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, retry_after.into());
+        return response;
+    }
+    next.run(request).await
+}
+
+async fn usage(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    key: Option<Extension<ApiKey>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let key = key.as_ref().map(|Extension(key)| key);
+    Json(quota::usage(&quota::client_id(key, &headers, peer.ip())))
 }
 
 async fn health() -> impl IntoResponse {
-    Json(json!({"status": "ok"}))
+    let circuits = upstream::snapshot();
+    let degraded = circuits.values().any(|c| c.state == CircuitState::Open);
+    Json(HealthResponse {
+        status: if degraded { "degraded" } else { "ok" },
+        circuits,
+    })
 }
 
-async fn extract_endpoint(Json(req): Json<ExtractRequest>) -> Response {
-    match extract::extract_article(&req.url).await {
-        Ok(result) => {
-            let response = ExtractResponse {
-                markdown: result.markdown,
-                title: result.title,
-                source_url: result.source_url,
-                images: result.images,
+async fn extract_endpoint(
+    Query(query): Query<ExtractQuery>,
+    Json(req): Json<ExtractRequest>,
+) -> Response {
+    let options = extract::ExtractOptions {
+        strategy: req.strategy,
+        language: req.language,
+        debug: query.debug,
+        subtitles: req.subtitles,
+        mirror_images: req.mirror_images,
+        probe_images: req.probe_images,
+        placeholders: req.placeholders,
+        strict: req.strict,
+    };
+    match extract::extract_article(&req.url, &options).await {
+        Ok(result) => (StatusCode::OK, Json(result.into_response())).into_response(),
+        Err(e) => error_response(&e),
+    }
+}
+
+async fn extract_html_endpoint(
+    Query(query): Query<ExtractHtmlQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(code) = query.language.as_deref() {
+        if !lang::is_valid_code(code) {
+            return error_response(&ExtractionError::InvalidLanguage(code.to_string()));
+        }
+    }
+    let options = extract::ExtractOptions {
+        strategy: query.strategy,
+        language: query.language,
+        debug: query.debug,
+        strict: query.strict,
+        ..Default::default()
+    };
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    match extract::extract_from_bytes(&body, content_type, &query.url, &options) {
+        Ok(result) => (StatusCode::OK, Json(result.into_response())).into_response(),
+        Err(e) => error_response(&e),
+    }
+}
+
+async fn editions_endpoint(Json(req): Json<EditionsRequest>) -> Response {
+    let options = extract::ExtractOptions {
+        strategy: req.strategy,
+        ..Default::default()
+    };
+    match editions::extract_editions(&req.url, &req.extract, &options).await {
+        Ok(editions) => {
+            let response = EditionsResponse {
+                source_url: req.url,
+                editions,
             };
             (StatusCode::OK, Json(response)).into_response()
         }
-        Err(e) => {
-            use extract::ExtractionError;
-            let (status, detail) = match &e {
-                ExtractionError::InvalidUrl(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
-                ExtractionError::NotHtml => (
-                    StatusCode::UNPROCESSABLE_ENTITY,
-                    "URL did not return HTML".to_string(),
-                ),
-                ExtractionError::Upstream => {
-                    (StatusCode::BAD_GATEWAY, "Upstream returned an error".to_string())
-                }
-                ExtractionError::Request(msg) => (
-                    StatusCode::BAD_GATEWAY,
-                    format!("Upstream request failed: {}", msg),
-                ),
-            };
-            (status, Json(json!({"detail": detail}))).into_response()
+        Err(e) => error_response(&e),
+    }
+}
+
+async fn align_endpoint(Json(req): Json<AlignRequest>) -> Response {
+    let options = extract::ExtractOptions::default();
+    let left = match extract::extract_from_html(&req.left.html, &req.left.url, &options) {
+        Ok(result) => result,
+        Err(e) => return error_response(&e),
+    };
+    let right = match extract::extract_from_html(&req.right.html, &req.right.url, &options) {
+        Ok(result) => result,
+        Err(e) => return error_response(&e),
+    };
+    let response = AlignResponse {
+        pairs: align::align(&left, &right),
+        left_title: left.title,
+        right_title: right.title,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+async fn image_proxy(Query(query): Query<ImageQuery>) -> Response {
+    match images::proxy(&query.url, query.w, query.format).await {
+        Ok((bytes, mime_type)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, mime_type),
+                (header::CACHE_CONTROL, "public, max-age=604800".to_string()),
+                (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => error_response(&e),
+    }
+}
+
+async fn image_file(Path(name): Path<String>) -> Response {
+    match images::read(&name).await {
+        Some((bytes, mime_type)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, mime_type),
+                (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+                (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            ],
+            bytes,
+        )
+            .into_response(),
+        None => api_error(
+            StatusCode::NOT_FOUND,
+            "image_not_found",
+            "Image not found".to_string(),
+            false,
+        ),
+    }
+}
+
+fn error_response(e: &ExtractionError) -> Response {
+    let status = match e {
+        ExtractionError::InvalidUrl(_)
+        | ExtractionError::InvalidRequest(_)
+        | ExtractionError::InvalidLanguage(_) => StatusCode::BAD_REQUEST,
+        ExtractionError::DisallowedByRobots(_) => StatusCode::FORBIDDEN,
+        ExtractionError::UpstreamNotFound(_) => StatusCode::NOT_FOUND,
+        ExtractionError::NotHtml
+        | ExtractionError::NotImage
+        | ExtractionError::DocumentTooLarge(_)
+        | ExtractionError::ExtractionFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ExtractionError::CircuitOpen { .. } | ExtractionError::OutboundRateLimited { .. } => {
+            StatusCode::SERVICE_UNAVAILABLE
         }
+        ExtractionError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        ExtractionError::RedirectNotAllowed(_)
+        | ExtractionError::BlockedAddress(_)
+        | ExtractionError::TooManyRedirects(_)
+        | ExtractionError::ResponseTooLarge(_)
+        | ExtractionError::Dns(_)
+        | ExtractionError::Tls(_)
+        | ExtractionError::Connect(_)
+        | ExtractionError::UpstreamServerError(_)
+        | ExtractionError::UpstreamStatus(_)
+        | ExtractionError::EmptyContent
+        | ExtractionError::Request(_) => StatusCode::BAD_GATEWAY,
+        ExtractionError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let body = ErrorResponse {
+        code: e.code(),
+        detail: e.to_string(),
+        upstream_status: e.upstream_status(),
+        retryable: e.is_retryable(),
+    };
+    let mut response = (status, Json(body)).into_response();
+    if let Some(retry_after) = e.retry_after() {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, retry_after.into());
     }
+    response
+}
+
+/// Error response for failures outside extraction (auth, quotas, lookups).
+fn api_error(status: StatusCode, code: &'static str, detail: String, retryable: bool) -> Response {
+    let body = ErrorResponse {
+        code,
+        detail,
+        upstream_status: None,
+        retryable,
+    };
+    (status, Json(body)).into_response()
 }
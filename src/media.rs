@@ -0,0 +1,523 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::Value;
+use url::Url;
+
+use crate::extract::collect_text;
+use crate::models::{MediaInfo, MediaKind, MediaSource, MediaTrack};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Data attributes that may carry an embedded media JSON document.
+const JSON_DATA_ATTRS: &[&str] = &[
+    "data-media",
+    "data-mediaitem",
+    "data-json",
+    "data-video-json",
+];
+
+static MEDIA_EXT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\.(mp4|m4v|webm|mov|m3u8|mp3|m4a|aac|ogg|oga|wav)(?:$|\?)").unwrap()
+});
+
+static AUDIO_EXT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(mp3|m4a|aac|oga|wav)(?:$|\?)").unwrap());
+
+static ISO_DURATION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^P(?:T(?:(\d+(?:\.\d+)?)H)?(?:(\d+(?:\.\d+)?)M)?(?:(\d+(?:\.\d+)?)S)?)$").unwrap()
+});
+
+// ── Public API ───────────────────────────────────────────────────────────────
+
+/// List the audio and video resources referenced by the page: `<audio>` and
+/// `<video>` elements, players that point at jw.org's media API through
+/// `data-jsonurl`, media JSON embedded in data attributes, JSON-LD
+/// `VideoObject`/`AudioObject` entries and `og:video`/`og:audio` meta tags.
+pub fn extract(document: &Html, base_url: &Url) -> Vec<MediaInfo> {
+    let mut items: Vec<MediaInfo> = Vec::new();
+
+    let element_sel = Selector::parse("video, audio").unwrap();
+    for el in document.select(&element_sel) {
+        push_unique(&mut items, from_media_element(el, base_url));
+    }
+
+    let data_sel = Selector::parse(
+        "[data-jsonurl], [data-media], [data-mediaitem], [data-json], [data-video-json]",
+    )
+    .unwrap();
+    for el in document.select(&data_sel) {
+        for attr in JSON_DATA_ATTRS {
+            if let Some(Ok(value)) = el.value().attr(attr).map(serde_json::from_str::<Value>) {
+                if let Some(item) = from_json(&value, base_url) {
+                    push_unique(&mut items, item);
+                }
+            }
+        }
+        if let Some(api) = el.value().attr("data-jsonurl") {
+            let kind = if class_id(el).to_lowercase().contains("audio") {
+                MediaKind::Audio
+            } else {
+                MediaKind::Video
+            };
+            let mut item = empty(kind);
+            item.api_url = resolve(base_url, api);
+            item.title = el.value().attr("data-title").map(|t| t.trim().to_string());
+            item.poster = el
+                .value()
+                .attr("data-poster")
+                .and_then(|p| resolve(base_url, p));
+            push_unique(&mut items, item);
+        }
+    }
+
+    let ld_sel = Selector::parse("script[type=\"application/ld+json\"]").unwrap();
+    for script in document.select(&ld_sel) {
+        if let Ok(value) = serde_json::from_str::<Value>(&collect_text(script)) {
+            collect_jsonld(&value, base_url, &mut items);
+        }
+    }
+
+    for (property, kind) in [
+        ("og:video", MediaKind::Video),
+        ("og:audio", MediaKind::Audio),
+    ] {
+        let sel = Selector::parse(&format!(
+            "meta[property=\"{p}\"], meta[property=\"{p}:secure_url\"]",
+            p = property
+        ))
+        .unwrap();
+        if let Some(url) = document
+            .select(&sel)
+            .find_map(|m| m.value().attr("content"))
+            .and_then(|c| resolve(base_url, c))
+        {
+            let mut item = empty(kind);
+            item.sources.push(MediaSource {
+                url,
+                mime_type: meta_content(document, &format!("{}:type", property)),
+                label: None,
+                width: meta_content(document, &format!("{}:width", property))
+                    .and_then(|w| w.parse().ok()),
+                height: meta_content(document, &format!("{}:height", property))
+                    .and_then(|h| h.parse().ok()),
+            });
+            push_unique(&mut items, item);
+        }
+    }
+
+    items.retain(|m| !m.sources.is_empty() || m.api_url.is_some());
+    items
+}
+
+// ── Sources ──────────────────────────────────────────────────────────────────
+
+fn from_media_element(el: ElementRef<'_>, base_url: &Url) -> MediaInfo {
+    let v = el.value();
+    let kind = if v.name() == "audio" {
+        MediaKind::Audio
+    } else {
+        MediaKind::Video
+    };
+    let mut item = empty(kind);
+
+    item.title = v
+        .attr("title")
+        .or_else(|| v.attr("aria-label"))
+        .map(|t| t.trim().to_string());
+    item.poster = v.attr("poster").and_then(|p| resolve(base_url, p));
+    item.duration = v.attr("data-duration").and_then(parse_duration);
+
+    if let Some(url) = v.attr("src").and_then(|s| resolve(base_url, s)) {
+        item.sources.push(MediaSource {
+            url,
+            mime_type: v.attr("type").map(|t| t.to_string()),
+            label: None,
+            width: v.attr("width").and_then(|w| w.parse().ok()),
+            height: v.attr("height").and_then(|h| h.parse().ok()),
+        });
+    }
+
+    for child in el.children().filter_map(ElementRef::wrap) {
+        let c = child.value();
+        match c.name() {
+            "source" => {
+                let Some(url) = c.attr("src").and_then(|s| resolve(base_url, s)) else {
+                    continue;
+                };
+                item.sources.push(MediaSource {
+                    url,
+                    mime_type: c.attr("type").map(|t| t.to_string()),
+                    label: c
+                        .attr("label")
+                        .or_else(|| c.attr("data-res"))
+                        .or_else(|| c.attr("res"))
+                        .or_else(|| c.attr("size"))
+                        .map(|l| l.to_string()),
+                    width: c.attr("data-width").and_then(|w| w.parse().ok()),
+                    height: c.attr("data-height").and_then(|h| h.parse().ok()),
+                });
+            }
+            "track" => {
+                if let Some(url) = c.attr("src").and_then(|s| resolve(base_url, s)) {
+                    item.tracks.push(MediaTrack {
+                        url,
+                        kind: c.attr("kind").map(|k| k.to_string()),
+                        language: c.attr("srclang").map(|l| l.to_string()),
+                        label: c.attr("label").map(|l| l.to_string()),
+                        text: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    item
+}
+
+/// Read a media item from jw.org's media JSON (mediator `files` lists and
+/// pub-media `files.<lang>.<format>` maps alike): every object with a media
+/// URL becomes a source, `subtitles.url` becomes a track.
+fn from_json(value: &Value, base_url: &Url) -> Option<MediaInfo> {
+    let mut item = empty(MediaKind::Video);
+    collect_json_files(value, base_url, &mut item);
+    if item.sources.is_empty() {
+        return None;
+    }
+    if item.sources.iter().all(is_audio) {
+        item.kind = MediaKind::Audio;
+    }
+    item.title = value
+        .get("title")
+        .and_then(Value::as_str)
+        .map(|t| t.to_string());
+    item.poster = value
+        .pointer("/images/wss/lg")
+        .or_else(|| value.pointer("/images/lss/lg"))
+        .or_else(|| value.get("poster"))
+        .and_then(Value::as_str)
+        .and_then(|p| resolve(base_url, p));
+    Some(item)
+}
+
+fn collect_json_files(value: &Value, base_url: &Url, item: &mut MediaInfo) {
+    match value {
+        Value::Object(map) => {
+            let url = ["progressiveDownloadURL", "url", "src"]
+                .iter()
+                .filter_map(|k| map.get(*k).and_then(Value::as_str))
+                .chain(value.pointer("/file/url").and_then(Value::as_str))
+                .find(|u| MEDIA_EXT_RE.is_match(u));
+            if let Some(url) = url.and_then(|u| resolve(base_url, u)) {
+                if !item.sources.iter().any(|s| s.url == url) {
+                    item.sources.push(MediaSource {
+                        url,
+                        mime_type: map
+                            .get("mimetype")
+                            .and_then(Value::as_str)
+                            .map(|m| m.to_string()),
+                        label: map
+                            .get("label")
+                            .and_then(Value::as_str)
+                            .map(|l| l.to_string()),
+                        width: map
+                            .get("frameWidth")
+                            .and_then(Value::as_u64)
+                            .map(|w| w as u32),
+                        height: map
+                            .get("frameHeight")
+                            .and_then(Value::as_u64)
+                            .map(|h| h as u32),
+                    });
+                }
+                if item.duration.is_none() {
+                    item.duration = map.get("duration").and_then(Value::as_f64);
+                }
+                if let Some(sub) = value.pointer("/subtitles/url").and_then(Value::as_str) {
+                    if let Some(url) = resolve(base_url, sub) {
+                        if !item.tracks.iter().any(|t| t.url == url) {
+                            item.tracks.push(MediaTrack {
+                                url,
+                                kind: Some("subtitles".to_string()),
+                                language: None,
+                                label: None,
+                                text: None,
+                            });
+                        }
+                    }
+                }
+                return;
+            }
+            for nested in map.values() {
+                collect_json_files(nested, base_url, item);
+            }
+        }
+        Value::Array(arr) => {
+            for nested in arr {
+                collect_json_files(nested, base_url, item);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_jsonld(value: &Value, base_url: &Url, items: &mut Vec<MediaInfo>) {
+    match value {
+        Value::Object(map) => {
+            let kind = match map.get("@type").and_then(Value::as_str) {
+                Some("VideoObject") => Some(MediaKind::Video),
+                Some("AudioObject") => Some(MediaKind::Audio),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                let mut item = empty(kind);
+                item.title = map
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(|n| n.to_string());
+                item.duration = map
+                    .get("duration")
+                    .and_then(Value::as_str)
+                    .and_then(parse_duration);
+                item.poster = map
+                    .get("thumbnailUrl")
+                    .and_then(|t| t.as_str().or_else(|| t.get(0).and_then(Value::as_str)))
+                    .and_then(|t| resolve(base_url, t));
+                if let Some(url) = map
+                    .get("contentUrl")
+                    .and_then(Value::as_str)
+                    .and_then(|u| resolve(base_url, u))
+                {
+                    item.sources.push(MediaSource {
+                        url,
+                        mime_type: map
+                            .get("encodingFormat")
+                            .and_then(Value::as_str)
+                            .map(|f| f.to_string()),
+                        label: None,
+                        width: None,
+                        height: None,
+                    });
+                }
+                push_unique(items, item);
+            }
+            for nested in map.values() {
+                collect_jsonld(nested, base_url, items);
+            }
+        }
+        Value::Array(arr) => {
+            for nested in arr {
+                collect_jsonld(nested, base_url, items);
+            }
+        }
+        _ => {}
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn empty(kind: MediaKind) -> MediaInfo {
+    MediaInfo {
+        kind,
+        title: None,
+        poster: None,
+        duration: None,
+        sources: Vec::new(),
+        tracks: Vec::new(),
+        api_url: None,
+    }
+}
+
+/// Add `item` unless an existing entry shares a source URL or API URL, in
+/// which case missing details are filled in from `item` instead.
+fn push_unique(items: &mut Vec<MediaInfo>, item: MediaInfo) {
+    let existing = items.iter_mut().find(|m| {
+        (item.api_url.is_some() && m.api_url == item.api_url)
+            || m.sources
+                .iter()
+                .any(|s| item.sources.iter().any(|o| o.url == s.url))
+    });
+    match existing {
+        Some(m) => {
+            m.title = m.title.take().or(item.title);
+            m.poster = m.poster.take().or(item.poster);
+            m.duration = m.duration.or(item.duration);
+            for source in item.sources {
+                if !m.sources.iter().any(|s| s.url == source.url) {
+                    m.sources.push(source);
+                }
+            }
+            for track in item.tracks {
+                if !m.tracks.iter().any(|t| t.url == track.url) {
+                    m.tracks.push(track);
+                }
+            }
+        }
+        None => items.push(item),
+    }
+}
+
+fn is_audio(source: &MediaSource) -> bool {
+    source
+        .mime_type
+        .as_deref()
+        .map(|m| m.starts_with("audio/"))
+        .unwrap_or_else(|| AUDIO_EXT_RE.is_match(&source.url))
+}
+
+fn resolve(base_url: &Url, href: &str) -> Option<String> {
+    let href = href.trim();
+    if href.is_empty() {
+        return None;
+    }
+    base_url.join(href).ok().map(|u| u.to_string())
+}
+
+fn class_id(el: ElementRef<'_>) -> String {
+    format!(
+        "{} {}",
+        el.value().id().unwrap_or(""),
+        el.value().classes().collect::<Vec<_>>().join(" ")
+    )
+}
+
+fn meta_content(document: &Html, property: &str) -> Option<String> {
+    let sel = Selector::parse(&format!("meta[property=\"{}\"]", property)).ok()?;
+    let content = document.select(&sel).next()?.value().attr("content")?;
+    Some(content.trim().to_string())
+}
+
+/// Parse a duration in seconds (`93.5`), clock form (`1:02:03`) or ISO 8601
+/// (`PT1M33S`).
+fn parse_duration(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if let Ok(seconds) = text.parse::<f64>() {
+        return Some(seconds);
+    }
+    if let Some(cap) = ISO_DURATION_RE.captures(text) {
+        let part = |i: usize| {
+            cap.get(i)
+                .and_then(|m| m.as_str().parse::<f64>().ok())
+                .unwrap_or(0.0)
+        };
+        return Some(part(1) * 3600.0 + part(2) * 60.0 + part(3));
+    }
+    if text.contains(':') {
+        return text.split(':').try_fold(0.0, |acc, part| {
+            part.parse::<f64>().ok().map(|p| acc * 60.0 + p)
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media(html: &str) -> Vec<MediaInfo> {
+        let document = Html::parse_document(html);
+        extract(
+            &document,
+            &Url::parse("https://www.jw.org/en/kids/").unwrap(),
+        )
+    }
+
+    #[test]
+    fn reads_video_sources_and_tracks() {
+        let items = media(include_str!("../tests/fixtures/media/video-page.html"));
+        let video = &items[0];
+        assert_eq!(video.kind, MediaKind::Video);
+        assert_eq!(video.title.as_deref(), Some("Intro"));
+        assert_eq!(
+            video.poster.as_deref(),
+            Some("https://www.jw.org/img/intro.jpg")
+        );
+        assert_eq!(video.duration, Some(3723.0));
+
+        let labels: Vec<_> = video.sources.iter().map(|s| s.label.as_deref()).collect();
+        assert_eq!(labels, [Some("240p"), Some("720p")]);
+        assert_eq!(video.sources[0].width, Some(426));
+        assert_eq!(video.sources[0].height, Some(240));
+        assert_eq!(video.sources[0].mime_type.as_deref(), Some("video/mp4"));
+
+        assert_eq!(video.tracks.len(), 1);
+        let track = &video.tracks[0];
+        assert_eq!(track.url, "https://www.jw.org/media/intro_E.vtt");
+        assert_eq!(track.kind.as_deref(), Some("subtitles"));
+        assert_eq!(track.language.as_deref(), Some("en"));
+        assert_eq!(track.label.as_deref(), Some("English"));
+    }
+
+    #[test]
+    fn keeps_jsonurl_players_as_api_links() {
+        let items = media(include_str!("../tests/fixtures/media/video-page.html"));
+        let audio = &items[1];
+        assert_eq!(audio.kind, MediaKind::Audio);
+        assert_eq!(audio.title.as_deref(), Some("Song 1"));
+        assert!(audio.sources.is_empty());
+        assert_eq!(
+            audio.api_url.as_deref(),
+            Some("https://b.jw-cdn.org/apis/pub-media/GETPUBMEDIALINKS?pub=sjjm&track=1&fileformat=MP3")
+        );
+    }
+
+    #[test]
+    fn reads_embedded_media_json_and_merges_json_ld() {
+        let items = media(include_str!("../tests/fixtures/media/video-page.html"));
+        // The JSON-LD VideoObject points at the same file as the player
+        // JSON, so it merges into that entry instead of adding another.
+        assert_eq!(items.len(), 4);
+        let video = &items[2];
+        assert_eq!(video.title.as_deref(), Some("Be Kind"));
+        assert_eq!(
+            video.poster.as_deref(),
+            Some("https://cms-imgp.jw-cdn.org/img/p/pk_wss_lg.jpg")
+        );
+        assert_eq!(video.duration, Some(125.4));
+        let sizes: Vec<_> = video.sources.iter().map(|s| (s.width, s.height)).collect();
+        assert_eq!(sizes, [(Some(854), Some(480)), (Some(1280), Some(720))]);
+        assert_eq!(
+            video.tracks[0].url,
+            "https://download-a.akamaihd.net/files/media_video/pk_E.vtt"
+        );
+    }
+
+    #[test]
+    fn reads_open_graph_video() {
+        let items = media(include_str!("../tests/fixtures/media/video-page.html"));
+        let og = &items[3].sources[0];
+        assert_eq!(
+            og.url,
+            "https://download-a.akamaihd.net/files/media_video/og_r720P.mp4"
+        );
+        assert_eq!(og.mime_type.as_deref(), Some("video/mp4"));
+        assert_eq!((og.width, og.height), (Some(1280), Some(720)));
+    }
+
+    #[test]
+    fn reads_json_ld_audio_objects() {
+        let items = media(include_str!("../tests/fixtures/media/audio-jsonld.html"));
+        assert_eq!(items.len(), 1);
+        let audio = &items[0];
+        assert_eq!(audio.kind, MediaKind::Audio);
+        assert_eq!(audio.title.as_deref(), Some("Great God, Jehovah"));
+        assert_eq!(audio.duration, Some(192.5));
+        assert_eq!(
+            audio.poster.as_deref(),
+            Some("https://www.jw.org/img/sjjm_12.jpg")
+        );
+        assert_eq!(audio.sources[0].mime_type.as_deref(), Some("audio/mpeg"));
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("93.5"), Some(93.5));
+        assert_eq!(parse_duration(" PT1M33S "), Some(93.0));
+        assert_eq!(parse_duration("PT1H"), Some(3600.0));
+        assert_eq!(parse_duration("PT1H2M3.5S"), Some(3723.5));
+        assert_eq!(parse_duration("2:05"), Some(125.0));
+        assert_eq!(parse_duration("1:02:03"), Some(3723.0));
+        assert_eq!(parse_duration("1:xx"), None);
+        assert_eq!(parse_duration("soon"), None);
+    }
+}
@@ -1,15 +1,326 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 pub struct ExtractRequest {
     pub url: String,
+    #[serde(default)]
+    pub strategy: ContainerStrategy,
+    /// jw.org language code (`es`, `pt-BR`, ...). Defaults to the language
+    /// in the URL path, or English.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Download WebVTT subtitle tracks and return their cues and transcript.
+    #[serde(default)]
+    pub subtitles: bool,
+    /// Download images and serve them from `/images/` instead of the CDN.
+    #[serde(default)]
+    pub mirror_images: bool,
+    /// Read image headers to report width, height, MIME type and byte size.
+    #[serde(default)]
+    pub probe_images: bool,
+    /// Compute a BlurHash and a tiny thumbnail for every image.
+    #[serde(default)]
+    pub placeholders: bool,
+    /// Fail with 422 instead of returning an extraction whose quality
+    /// status is `failed`.
+    #[serde(default)]
+    pub strict: bool,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Deserialize)]
+pub struct ExtractQuery {
+    #[serde(default)]
+    pub debug: bool,
+}
+
+/// Query of `POST /extract/html`; the body is the raw page.
+#[derive(Debug, Deserialize)]
+pub struct ExtractHtmlQuery {
+    /// URL the page was saved from, used to resolve links and pick site rules.
+    pub url: String,
+    #[serde(default)]
+    pub strategy: ContainerStrategy,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub debug: bool,
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// Query of `GET /image`.
+#[derive(Debug, Deserialize)]
+pub struct ImageQuery {
+    pub url: String,
+    /// Target width in pixels; images are never enlarged.
+    #[serde(default)]
+    pub w: Option<u32>,
+    /// Output format; defaults to the source format.
+    #[serde(default)]
+    pub format: Option<ImageFormat>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+/// How the main content block is chosen.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerStrategy {
+    /// First `article`, then `main`, then the longest content-like div.
+    #[default]
+    Heuristic,
+    /// Readability-style scoring of text density, link density and class weight.
+    Scoring,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
 pub struct ImageInfo {
+    /// Stable id of the underlying asset, the same for every size and query
+    /// string of one CDN image.
+    pub image_id: String,
     pub url: String,
     pub alt: Option<String>,
     pub caption: Option<String>,
+    /// CDN URL of a mirrored image; `url` then holds the local path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_url: Option<String>,
+    /// Pixel size, MIME type and file size filled in by `probe_images`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub byte_size: Option<u64>,
+    /// Every resolution the page offers, smallest first; empty when there is
+    /// only `url`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<ImageVariant>,
+    /// Low-resolution preview filled in by `placeholders`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<ImagePlaceholder>,
+}
+
+/// Preview to show while an image loads.
+#[derive(Debug, Serialize, Clone)]
+pub struct ImagePlaceholder {
+    pub blurhash: String,
+    /// Tiny JPEG as a `data:` URI.
+    pub thumbnail: String,
+}
+
+/// One candidate URL of a responsive image.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ImageVariant {
+    pub url: String,
+    /// Width from a `srcset` `w` descriptor.
+    pub width: Option<u32>,
+    /// Pixel density from a `srcset` `x` descriptor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub density: Option<f64>,
+    /// jw.org size label: `xs`, `s`, `m`, `l` or `xl`.
+    pub size: Option<String>,
+    /// `type` of the `<picture>` `<source>` the URL came from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Audio,
+    Video,
+}
+
+/// An audio or video resource referenced by the article page.
+#[derive(Debug, Serialize, Clone)]
+pub struct MediaInfo {
+    pub kind: MediaKind,
+    pub title: Option<String>,
+    pub poster: Option<String>,
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+    /// One entry per available file/quality.
+    pub sources: Vec<MediaSource>,
+    pub tracks: Vec<MediaTrack>,
+    /// jw.org media API URL (`data-jsonurl`) the player loads its files from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MediaSource {
+    pub url: String,
+    pub mime_type: Option<String>,
+    /// Quality label such as `720p`.
+    pub label: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// A subtitle or caption track.
+#[derive(Debug, Serialize, Clone)]
+pub struct MediaTrack {
+    pub url: String,
+    pub kind: Option<String>,
+    pub language: Option<String>,
+    pub label: Option<String>,
+    /// Downloaded subtitle text (only when `subtitles` is requested).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<TrackText>,
+}
+
+/// Parsed contents of a WebVTT track.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct TrackText {
+    pub cues: Vec<SubtitleCue>,
+    /// Cue text joined into paragraphs, with repeated lines dropped.
+    pub transcript: String,
+    /// Why the track could not be downloaded or parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One timed cue; times are in seconds.
+#[derive(Debug, Serialize, Clone)]
+pub struct SubtitleCue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// `GET /health` response.
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// `ok`, or `degraded` while any upstream circuit is open.
+    pub status: &'static str,
+    /// Circuit breaker state per upstream host.
+    pub circuits: BTreeMap<String, CircuitStatus>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CircuitStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    /// Open period is over; the next request is a trial.
+    HalfOpen,
+}
+
+/// How the extraction arrived at its result.
+#[derive(Debug, Serialize, Clone)]
+pub struct ExtractDebug {
+    pub strategy: ContainerStrategy,
+    /// Language used for Accept-Language and metadata filtering.
+    pub language: String,
+    /// Which step picked the container: `rule_selector`, `article`, `main`,
+    /// `keyword_div`, `scoring` or `body`.
+    pub container_source: &'static str,
+    /// Content score of the winning block (scoring strategy only).
+    pub container_score: Option<f64>,
+    /// Detailed trace, only filled in when `?debug=true` is requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<ExtractExplain>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ExtractExplain {
+    /// Name of the site rule that was applied.
+    pub rule: String,
+    /// CSS paths of the container element(s) that were walked.
+    pub container_paths: Vec<String>,
+    /// `rule_selector`, `h1`, `title`, `fallback` or `none`.
+    pub title_source: &'static str,
+    pub skipped: Vec<SkippedElement>,
+    pub fallback_image: Option<FallbackImageDebug>,
+}
+
+/// An element the walker dropped from the markdown, and why.
+#[derive(Debug, Serialize, Clone)]
+pub struct SkippedElement {
+    pub path: String,
+    pub reason: SkipReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// script, style, form, audio, video, ...
+    HardSkipTag,
+    /// Matched one of the site rule's `remove_selectors`.
+    RuleRemoveSelector,
+    /// nav, footer or aside.
+    ChromeTag,
+    HeaderOutsideArticle,
+    AriaLabelControl,
+    TitleAttrControl,
+    PlayButton,
+    /// Player-like class/id with little text and no image.
+    PlayerClass,
+    // The remaining reasons only apply to short blocks (≤ 250 chars).
+    MetadataClass,
+    PublicationName,
+    IssuePattern,
+    LanguageBanner,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FallbackImageDebug {
+    /// `meta:<property>`, `json_ld`, `image_link`, `cms_url` or `akamai_url`.
+    pub source: String,
+    pub url: String,
+    /// False when the content already had images and the fallback was unused.
+    pub used: bool,
+}
+
+/// Structured data from Watchtower Online Library documents.
+#[derive(Debug, Serialize, Clone)]
+pub struct WolData {
+    pub paragraphs: Vec<WolParagraph>,
+    pub study_questions: Vec<StudyQuestion>,
+    pub references: Vec<WolReference>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WolParagraph {
+    /// WOL paragraph id (`data-pid`), the target of `#h=` links.
+    pub pid: Option<u32>,
+    /// Printed paragraph number, if the paragraph is numbered.
+    pub number: Option<u32>,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StudyQuestion {
+    pub pid: Option<u32>,
+    /// Paragraph numbers the question covers.
+    pub paragraphs: Vec<u32>,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WolReference {
+    /// `scripture`, `footnote`, `publication`, `document` or `bible`.
+    pub kind: String,
+    pub text: String,
+    pub url: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -18,4 +329,132 @@ pub struct ExtractResponse {
     pub title: Option<String>,
     pub source_url: String,
     pub images: Vec<ImageInfo>,
+    pub media: Vec<MediaInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wol: Option<WolData>,
+    pub quality: ExtractQuality,
+    pub debug: ExtractDebug,
+}
+
+/// How much of an article the extraction is likely to have captured.
+#[derive(Debug, Serialize, Clone)]
+pub struct ExtractQuality {
+    pub status: QualityStatus,
+    /// 0.0 (nothing usable) to 1.0.
+    pub confidence: f64,
+    /// Words in the markdown, excluding the title and images.
+    pub word_count: usize,
+    /// Share of the page's visible words that made it into the markdown.
+    pub text_ratio: f64,
+    pub has_title: bool,
+    /// `few_words`, `low_text_ratio` and/or `missing_title`.
+    pub issues: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityStatus {
+    Ok,
+    /// Usable, but short, missing a title or a small part of the page.
+    LowConfidence,
+    /// Empty or title-only.
+    Failed,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditionsRequest {
+    pub url: String,
+    /// Language codes of the editions to extract; the rest are only listed.
+    #[serde(default)]
+    pub extract: Vec<String>,
+    #[serde(default)]
+    pub strategy: ContainerStrategy,
+}
+
+/// One language edition of an article.
+#[derive(Debug, Serialize)]
+pub struct Edition {
+    /// `None` for a requested language the page does not link to.
+    pub url: Option<String>,
+    /// Language name as shown in the page's language picker.
+    pub language_name: Option<String>,
+    /// Article title in this language. Known for the requested page and for
+    /// editions listed in `extract`; other editions are only discovered from
+    /// links, which carry no title, so theirs is `None`.
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub article: Option<ExtractResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EditionsResponse {
+    pub source_url: String,
+    /// Editions keyed by language code.
+    pub editions: BTreeMap<String, Edition>,
+}
+
+/// A saved page to align; `url` is only used to resolve relative links and
+/// pick the language, nothing is fetched.
+#[derive(Debug, Deserialize)]
+pub struct AlignInput {
+    pub html: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlignRequest {
+    pub left: AlignInput,
+    pub right: AlignInput,
+}
+
+/// Blocks from each edition that correspond to each other. Either side may
+/// hold several paragraphs (merged) or none (unmatched).
+#[derive(Debug, Serialize)]
+pub struct AlignedPair {
+    /// `heading`, `image` or `paragraph`.
+    pub kind: &'static str,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlignResponse {
+    pub left_title: Option<String>,
+    pub right_title: Option<String>,
+    pub pairs: Vec<AlignedPair>,
+}
+
+/// `GET /usage` response: the caller's request counts in the current windows.
+#[derive(Debug, Serialize)]
+pub struct UsageResponse {
+    /// How the caller is identified (`key:...`, `consumer:...` or `ip:...`).
+    pub client: String,
+    pub tier: String,
+    pub windows: Vec<UsageWindow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageWindow {
+    /// `minute`, `hour` or `day` (UTC).
+    pub window: &'static str,
+    /// `null` when the tier has no limit for this window.
+    pub limit: Option<u32>,
+    pub used: u32,
+    pub remaining: Option<u32>,
+    /// Seconds until the window resets.
+    pub reset_seconds: u64,
+}
+
+/// Body of every error response.
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    /// Stable, machine-readable error code (e.g. `upstream_timeout`).
+    pub code: &'static str,
+    pub detail: String,
+    /// Status jw.org answered with, when the error came from it.
+    pub upstream_status: Option<u16>,
+    /// Whether the same request may succeed if repeated later.
+    pub retryable: bool,
 }
@@ -0,0 +1,227 @@
+use std::error::Error;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+// ── Errors ───────────────────────────────────────────────────────────────────
+
+/// A redirect pointed outside the hosts the request was allowed to reach.
+#[derive(Debug, thiserror::Error)]
+#[error("redirect to {0} is not allowed")]
+pub struct BlockedRedirect(pub String);
+
+/// A host resolved only to private, loopback or otherwise non-public addresses.
+#[derive(Debug, thiserror::Error)]
+#[error("{0} does not resolve to a public address")]
+pub struct BlockedAddress(pub String);
+
+/// More redirects than allowed were returned.
+#[derive(Debug, thiserror::Error)]
+#[error("more than {0} redirects")]
+pub struct TooManyRedirects(pub usize);
+
+/// A host name could not be resolved.
+#[derive(Debug, thiserror::Error)]
+#[error("cannot resolve {host}: {source}")]
+pub struct DnsFailure {
+    pub host: String,
+    source: io::Error,
+}
+
+/// Find an error of type `T` in the source chain of `err`. An `io::Error`'s
+/// `source` skips the error it wraps, so that one is looked at as well.
+pub fn find_cause<'a, T: Error + 'static>(err: &'a (dyn Error + 'static)) -> Option<&'a T> {
+    let mut current = Some(err);
+    while let Some(e) = current {
+        if let Some(found) = e.downcast_ref::<T>() {
+            return Some(found);
+        }
+        current = match e.downcast_ref::<io::Error>().and_then(io::Error::get_ref) {
+            Some(inner) => Some(inner),
+            None => e.source(),
+        };
+    }
+    None
+}
+
+// ── DNS ──────────────────────────────────────────────────────────────────────
+
+/// Resolver that drops non-public addresses, so a jw.org name pointing at an
+/// internal address cannot be used to reach services inside the cluster.
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let public: Vec<SocketAddr> = match tokio::net::lookup_host((name.as_str(), 0)).await {
+                Ok(addrs) => addrs.filter(|addr| is_public(addr.ip())).collect(),
+                Err(source) => {
+                    return Err(Box::new(DnsFailure { host, source }) as Box<dyn Error + Send + Sync>)
+                }
+            };
+            if public.is_empty() {
+                return Err(Box::new(BlockedAddress(host)) as Box<dyn Error + Send + Sync>);
+            }
+            Ok(Box::new(public.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// True for globally routable unicast addresses.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match embedded_v4(v6) {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(v6),
+        },
+    }
+}
+
+/// The IPv4 address an IPv6 address stands for: v4-mapped (`::ffff:0:0/96`),
+/// NAT64 (`64:ff9b::/96`) and 6to4 (`2002::/16`) addresses reach that host.
+fn embedded_v4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return Some(v4);
+    }
+    let [a, b, c, d, e, f, g, h] = ip.segments();
+    let v4 = |hi: u16, lo: u16| Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo));
+    match (a, b, c, d, e, f) {
+        (0x64, 0xff9b, 0, 0, 0, 0) => Some(v4(g, h)),
+        (0x2002, ..) => Some(v4(b, c)),
+        _ => None,
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // "This network", shared address space (CGNAT), IETF protocol
+        // assignments, benchmarking and reserved ranges.
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (b == 18 || b == 19))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let [first, second, ..] = ip.segments();
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // IPv4-compatible (::/96) and local-use NAT64 (64:ff9b:1::/48)
+        // addresses, whose IPv4 host is not checked.
+        || ip.segments()[..6] == [0; 6]
+        || (first == 0x64 && second == 0xff9b)
+        // Unique local (fc00::/7), link-local (fe80::/10) and documentation
+        // (2001:db8::/32) ranges.
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first == 0x2001 && second == 0x0db8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Like hyper's connect error: a wrapper whose source is an `io::Error`.
+    #[derive(Debug, thiserror::Error)]
+    #[error("client error (Connect)")]
+    struct Connect(#[source] io::Error);
+
+    #[test]
+    fn finds_cause_wrapped_in_io_error() {
+        let tls = rustls::Error::General("handshake failed".to_string());
+        let err = Connect(io::Error::new(io::ErrorKind::InvalidData, tls));
+        let found = find_cause::<rustls::Error>(&err).unwrap();
+        assert_eq!(found.to_string(), "unexpected error: handshake failed");
+        assert!(find_cause::<BlockedAddress>(&err).is_none());
+    }
+
+    #[test]
+    fn finds_cause_below_wrapped_error() {
+        let dns = DnsFailure {
+            host: "www.jw.org".to_string(),
+            source: io::Error::other("no such host"),
+        };
+        let err = Connect(io::Error::other(dns));
+        assert_eq!(find_cause::<DnsFailure>(&err).unwrap().host, "www.jw.org");
+        assert!(find_cause::<io::Error>(&err).is_some());
+    }
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn allows_public_addresses() {
+        for ip in ["104.18.20.1", "8.8.8.8", "2606:4700::1", "::ffff:8.8.8.8"] {
+            assert!(public(ip), "{ip}");
+        }
+    }
+
+    #[test]
+    fn blocks_non_public_v4() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "100.64.0.1",
+            "100.127.255.254",
+            "169.254.169.254",
+            "192.0.0.8",
+            "0.0.0.0",
+            "198.18.0.1",
+            "240.0.0.1",
+            "255.255.255.255",
+        ] {
+            assert!(!public(ip), "{ip}");
+        }
+        assert!(public("100.128.0.1"));
+        assert!(public("192.0.1.1"));
+    }
+
+    #[test]
+    fn blocks_non_public_v6() {
+        for ip in [
+            "::1",
+            "::",
+            "fd00::1",
+            "fc12::1",
+            "fe80::1",
+            "2001:db8::1",
+            "ff02::1",
+        ] {
+            assert!(!public(ip), "{ip}");
+        }
+    }
+
+    #[test]
+    fn checks_ipv4_embedded_in_v6() {
+        // v4-mapped, NAT64 and 6to4 forms of private and public hosts.
+        for ip in [
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "64:ff9b::7f00:1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:c0a8:0101::1",
+            "2002:0a00:0001::1",
+            "::127.0.0.1",
+            "64:ff9b:1::808:808",
+        ] {
+            assert!(!public(ip), "{ip}");
+        }
+        assert!(public("64:ff9b::808:808"));
+        assert!(public("2002:0808:0808::1"));
+    }
+}
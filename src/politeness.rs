@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::extract::ExtractionError;
+
+// ── Configuration ────────────────────────────────────────────────────────────
+
+const DEFAULT_RATE: f64 = 5.0;
+const DEFAULT_BURST: f64 = 10.0;
+const DEFAULT_MAX_CONNECTIONS: usize = 8;
+/// Requests that would have to queue longer than this fail instead.
+const MAX_QUEUE_WAIT: Duration = Duration::from_secs(15);
+
+/// Outbound limits per upstream host, from the environment.
+struct Limits {
+    /// Sustained requests per second (`JW_NEWS_READER_UPSTREAM_RPS`).
+    rate: f64,
+    /// Requests allowed in a burst (`JW_NEWS_READER_UPSTREAM_BURST`).
+    burst: f64,
+    /// Concurrent connections (`JW_NEWS_READER_UPSTREAM_CONNECTIONS`).
+    max_connections: usize,
+}
+
+static LIMITS: Lazy<Limits> = Lazy::new(|| {
+    fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+        std::env::var(name).ok()?.parse().ok()
+    }
+    Limits {
+        rate: env("JW_NEWS_READER_UPSTREAM_RPS")
+            .filter(|r: &f64| *r > 0.0)
+            .unwrap_or(DEFAULT_RATE),
+        burst: env("JW_NEWS_READER_UPSTREAM_BURST")
+            .filter(|b: &f64| *b >= 1.0)
+            .unwrap_or(DEFAULT_BURST),
+        max_connections: env("JW_NEWS_READER_UPSTREAM_CONNECTIONS")
+            .filter(|c: &usize| *c > 0)
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS),
+    }
+});
+
+static HOSTS: Lazy<Mutex<HashMap<String, HostState>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct HostState {
+    bucket: Bucket,
+    connections: Arc<Semaphore>,
+}
+
+// ── Token bucket ─────────────────────────────────────────────────────────────
+
+/// Token bucket for one host. Requests that find it empty join a queue and
+/// take their token only once their wait is over, so a request cancelled
+/// while waiting costs nothing.
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+    /// Requests waiting for a token.
+    queued: usize,
+}
+
+impl Bucket {
+    fn new(rate: f64, burst: f64, now: Instant) -> Self {
+        Bucket {
+            rate,
+            burst,
+            tokens: burst,
+            updated: now,
+            queued: 0,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = self.updated.max(now);
+    }
+
+    /// Time until `tokens` more tokens have accumulated.
+    fn time_for(&self, tokens: f64) -> Duration {
+        Duration::from_secs_f64((tokens - self.tokens).max(0.0) / self.rate)
+    }
+
+    /// Admit a new request: take a token if one is free and nobody is
+    /// queued (`Ok(ZERO)`), or join the queue and return the expected wait.
+    /// Fails with the wait if it would exceed `max_wait`.
+    fn enqueue(&mut self, now: Instant, max_wait: Duration) -> Result<Duration, Duration> {
+        self.refill(now);
+        let wait = self.time_for(1.0 + self.queued as f64);
+        if wait > max_wait {
+            return Err(wait);
+        }
+        if wait.is_zero() {
+            self.tokens -= 1.0;
+        } else {
+            self.queued += 1;
+        }
+        Ok(wait)
+    }
+
+    /// Take a token for a queued request whose wait is over, leaving the
+    /// queue; otherwise return how much longer it has to wait.
+    fn dequeue(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return Some(self.time_for(1.0));
+        }
+        self.tokens -= 1.0;
+        self.queued = self.queued.saturating_sub(1);
+        None
+    }
+
+    /// A queued request gave up without taking a token.
+    fn leave(&mut self) {
+        self.queued = self.queued.saturating_sub(1);
+    }
+}
+
+/// A place in `host`'s queue; leaves it if dropped before a token is taken.
+struct Queued<'a> {
+    host: &'a str,
+    waiting: bool,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        if self.waiting {
+            if let Some(state) = HOSTS.lock().unwrap().get_mut(self.host) {
+                state.bucket.leave();
+            }
+        }
+    }
+}
+
+// ── Admission ────────────────────────────────────────────────────────────────
+
+/// A response together with the connection slot it occupies; the slot is
+/// released once the body has been read and the response dropped.
+pub struct Admitted<T> {
+    inner: T,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<T> Deref for Admitted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for Admitted<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+/// A reserved request slot for a host; attach it to the response with
+/// [`Slot::admit`].
+pub struct Slot(OwnedSemaphorePermit);
+
+impl Slot {
+    pub fn admit<T>(self, inner: T) -> Admitted<T> {
+        Admitted {
+            inner,
+            _permit: self.0,
+        }
+    }
+}
+
+/// Wait for a token from `host`'s bucket and a free connection slot. Fails
+/// when the queue ahead is longer than `MAX_QUEUE_WAIT`.
+pub async fn acquire(host: &str) -> Result<Slot, ExtractionError> {
+    let (wait, connections) = {
+        let mut hosts = HOSTS.lock().unwrap();
+        let now = Instant::now();
+        let state = hosts.entry(host.to_string()).or_insert_with(|| HostState {
+            bucket: Bucket::new(LIMITS.rate, LIMITS.burst, now),
+            connections: Arc::new(Semaphore::new(LIMITS.max_connections)),
+        });
+        let wait = state.bucket.enqueue(now, MAX_QUEUE_WAIT).map_err(|wait| {
+            ExtractionError::OutboundRateLimited {
+                host: host.to_string(),
+                retry_after: wait.as_secs().max(1),
+            }
+        })?;
+        (wait, state.connections.clone())
+    };
+
+    if !wait.is_zero() {
+        tracing::debug!("rate limiting {}: waiting {:?}", host, wait);
+        let mut queued = Queued {
+            host,
+            waiting: true,
+        };
+        let mut wait = wait;
+        loop {
+            tokio::time::sleep(wait).await;
+            let mut hosts = HOSTS.lock().unwrap();
+            let Some(state) = hosts.get_mut(host) else {
+                break;
+            };
+            match state.bucket.dequeue(Instant::now()) {
+                Some(more) => wait = more,
+                None => break,
+            }
+        }
+        queued.waiting = false;
+    }
+    let permit = connections
+        .acquire_owned()
+        .await
+        .map_err(|e| ExtractionError::Request(e.to_string()))?;
+    Ok(Slot(permit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_WAIT: Duration = Duration::from_secs(2);
+
+    fn secs(s: f64) -> Duration {
+        Duration::from_secs_f64(s)
+    }
+
+    #[test]
+    fn allows_a_burst_then_queues() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(2.0, 3.0, now);
+        for _ in 0..3 {
+            assert_eq!(bucket.enqueue(now, MAX_WAIT), Ok(Duration::ZERO));
+        }
+        assert_eq!(bucket.enqueue(now, MAX_WAIT), Ok(secs(0.5)));
+        // The next request waits behind the queued one.
+        assert_eq!(bucket.enqueue(now, MAX_WAIT), Ok(secs(1.0)));
+        assert_eq!(bucket.queued, 2);
+    }
+
+    #[test]
+    fn refills_up_to_the_burst() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(2.0, 3.0, now);
+        for _ in 0..3 {
+            bucket.enqueue(now, MAX_WAIT).unwrap();
+        }
+        bucket.refill(now + secs(1.0));
+        assert_eq!(bucket.tokens, 2.0);
+        bucket.refill(now + secs(60.0));
+        assert_eq!(bucket.tokens, 3.0);
+    }
+
+    #[test]
+    fn rejects_waits_beyond_the_limit() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(1.0, 1.0, now);
+        bucket.enqueue(now, MAX_WAIT).unwrap();
+        assert_eq!(bucket.enqueue(now, MAX_WAIT), Ok(secs(1.0)));
+        assert_eq!(bucket.enqueue(now, MAX_WAIT), Ok(secs(2.0)));
+        assert_eq!(bucket.enqueue(now, MAX_WAIT), Err(secs(3.0)));
+        assert_eq!(bucket.queued, 2);
+    }
+
+    #[test]
+    fn queued_requests_take_tokens_only_when_done_waiting() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(1.0, 1.0, now);
+        bucket.enqueue(now, MAX_WAIT).unwrap();
+        bucket.enqueue(now, MAX_WAIT).unwrap();
+        bucket.enqueue(now, MAX_WAIT).unwrap();
+        assert_eq!(bucket.tokens, 0.0);
+
+        // Too early: keep waiting.
+        assert_eq!(bucket.dequeue(now + secs(0.5)), Some(secs(0.5)));
+        assert_eq!(bucket.dequeue(now + secs(1.0)), None);
+        assert_eq!(bucket.queued, 1);
+
+        // The other one is cancelled, so a newcomer does not wait for it.
+        bucket.leave();
+        assert_eq!(bucket.enqueue(now + secs(1.0), MAX_WAIT), Ok(secs(1.0)));
+    }
+}
@@ -0,0 +1,238 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node};
+
+use crate::models::{ExtractQuality, QualityStatus};
+
+// ── Thresholds ───────────────────────────────────────────────────────────────
+
+/// Fewer words than this and the extraction has failed (empty or title-only).
+const MIN_WORDS: usize = 20;
+/// Fewer words than this and the article is suspiciously short.
+const LOW_WORDS: usize = 100;
+/// Word count that earns full marks for length.
+const GOOD_WORDS: usize = 250;
+/// Share of the page's words below which the container probably missed
+/// most of the article.
+const LOW_TEXT_RATIO: f64 = 0.1;
+/// Share of the page's words that earns full marks for coverage.
+const GOOD_TEXT_RATIO: f64 = 0.3;
+
+/// Weights of word count, text ratio and title in the confidence score.
+const WORDS_WEIGHT: f64 = 0.6;
+const RATIO_WEIGHT: f64 = 0.25;
+const TITLE_WEIGHT: f64 = 0.15;
+
+static IMAGE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!\[[^\]]*\]\([^)]*\)").unwrap());
+static LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+
+// ── Assessment ───────────────────────────────────────────────────────────────
+
+/// Judge an extraction by the words in its markdown (title and images
+/// excluded), the share of the page's visible words it kept, and whether a
+/// title was found.
+pub fn assess(markdown: &str, title: Option<&str>, document: &Html) -> ExtractQuality {
+    let heading = title.map(|t| format!("# {}", t));
+    let body: String = markdown
+        .lines()
+        .filter(|line| Some(line.trim()) != heading.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let body = IMAGE_RE.replace_all(&body, " ");
+    let body = LINK_RE.replace_all(&body, "$1");
+    let word_count = count_words(&body);
+
+    let mut page_text = String::new();
+    visible_text(document.root_element(), &mut page_text);
+    let page_words = count_words(&page_text);
+    let text_ratio = if page_words == 0 {
+        0.0
+    } else {
+        (word_count as f64 / page_words as f64).min(1.0)
+    };
+    let has_title = title.is_some_and(|t| !t.trim().is_empty());
+
+    let mut issues = Vec::new();
+    if word_count < LOW_WORDS {
+        issues.push("few_words");
+    }
+    if text_ratio < LOW_TEXT_RATIO {
+        issues.push("low_text_ratio");
+    }
+    if !has_title {
+        issues.push("missing_title");
+    }
+
+    let status = if word_count < MIN_WORDS {
+        QualityStatus::Failed
+    } else if issues.is_empty() {
+        QualityStatus::Ok
+    } else {
+        QualityStatus::LowConfidence
+    };
+
+    let confidence = WORDS_WEIGHT * (word_count as f64 / GOOD_WORDS as f64).min(1.0)
+        + RATIO_WEIGHT * (text_ratio / GOOD_TEXT_RATIO).min(1.0)
+        + if has_title { TITLE_WEIGHT } else { 0.0 };
+    let confidence = if status == QualityStatus::Failed {
+        confidence.min(0.2)
+    } else {
+        confidence
+    };
+
+    ExtractQuality {
+        status,
+        confidence: (confidence * 100.0).round() / 100.0,
+        word_count,
+        text_ratio: (text_ratio * 100.0).round() / 100.0,
+        has_title,
+        issues,
+    }
+}
+
+/// Whitespace-separated tokens with at least one letter or digit. Chinese
+/// and Japanese characters count as one word each, since those languages
+/// are written without spaces.
+fn count_words(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|token| {
+            let cjk = token.chars().filter(|c| is_cjk(*c)).count();
+            if cjk > 0 {
+                cjk + usize::from(token.chars().any(|c| c.is_alphanumeric() && !is_cjk(c)))
+            } else {
+                usize::from(token.chars().any(char::is_alphanumeric))
+            }
+        })
+        .sum()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4dbf}'   // CJK Extension A
+        | '\u{4e00}'..='\u{9fff}'   // CJK Unified Ideographs
+        | '\u{f900}'..='\u{faff}') // CJK Compatibility Ideographs
+}
+
+/// Text a reader could see: everything except scripts, styles and other
+/// non-rendered elements.
+fn visible_text(el: ElementRef<'_>, out: &mut String) {
+    if matches!(
+        el.value().name(),
+        "script" | "style" | "noscript" | "template" | "head"
+    ) {
+        return;
+    }
+    for child in el.children() {
+        match child.value() {
+            Node::Text(text) => {
+                out.push_str(text);
+                out.push(' ');
+            }
+            Node::Element(_) => {
+                if let Some(child_el) = ElementRef::wrap(child) {
+                    visible_text(child_el, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` distinct-looking words.
+    fn words(n: usize) -> String {
+        (0..n)
+            .map(|i| format!("word{}", i))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Assess `markdown` as extracted from a page whose body is `page`.
+    fn assess_page(markdown: &str, title: Option<&str>, page: &str) -> ExtractQuality {
+        let document = Html::parse_document(&format!(
+            "<html><head><title>T</title><script>var hidden = 1;</script></head>\
+             <body>{}</body></html>",
+            page
+        ));
+        assess(markdown, title, &document)
+    }
+
+    #[test]
+    fn empty_markdown_fails() {
+        let quality = assess_page("", None, "<nav>Home About Contact</nav>");
+        assert_eq!(quality.status, QualityStatus::Failed);
+        assert_eq!(quality.word_count, 0);
+        assert_eq!(quality.confidence, 0.0);
+        assert_eq!(
+            quality.issues,
+            ["few_words", "low_text_ratio", "missing_title"]
+        );
+    }
+
+    #[test]
+    fn title_only_fails() {
+        let quality = assess_page(
+            "# Be Kind\n\n![Sea](https://x/a.jpg)",
+            Some("Be Kind"),
+            "<h1>Be Kind</h1><nav>Home About Contact</nav>",
+        );
+        assert_eq!(quality.status, QualityStatus::Failed);
+        assert_eq!(quality.word_count, 0);
+        assert!(quality.has_title);
+        assert_eq!(quality.confidence, 0.15);
+    }
+
+    #[test]
+    fn short_articles_fail_below_min_words() {
+        // A complete eight-word notice is still too short to trust.
+        let text = "The meeting is moved to Sunday at noon.";
+        let quality = assess_page(text, Some("Notice"), &format!("<p>{}</p>", text));
+        assert_eq!(quality.word_count, 8);
+        assert_eq!(quality.text_ratio, 1.0);
+        assert_eq!(quality.status, QualityStatus::Failed);
+        assert_eq!(quality.confidence, 0.2);
+
+        let text = words(50);
+        let quality = assess_page(&text, Some("Notice"), &format!("<p>{}</p>", text));
+        assert_eq!(quality.status, QualityStatus::LowConfidence);
+        assert_eq!(quality.issues, ["few_words"]);
+        assert_eq!(quality.confidence, 0.52);
+    }
+
+    #[test]
+    fn normal_article_is_ok() {
+        let text = words(300);
+        let markdown = format!("# Title\n\n{} [a link](https://www.jw.org/x)", text);
+        let page = format!(
+            "<h1>Title</h1><p>{} a link</p><footer>{}</footer>",
+            text,
+            words(100)
+        );
+        let quality = assess_page(&markdown, Some("Title"), &page);
+        assert_eq!(quality.status, QualityStatus::Ok);
+        assert_eq!(quality.word_count, 302);
+        assert_eq!(quality.text_ratio, 0.75);
+        assert!(quality.issues.is_empty());
+        assert_eq!(quality.confidence, 1.0);
+    }
+
+    #[test]
+    fn low_text_ratio_lowers_confidence() {
+        let text = words(150);
+        let page = format!("<p>{}</p><aside>{}</aside>", text, words(1850));
+        let quality = assess_page(&text, Some("Title"), &page);
+        assert_eq!(quality.status, QualityStatus::LowConfidence);
+        assert_eq!(quality.issues, ["low_text_ratio"]);
+        assert_eq!(quality.text_ratio, 0.08);
+    }
+
+    #[test]
+    fn counts_cjk_characters_as_words() {
+        assert_eq!(count_words("平和 な 世界 2024"), 6);
+        assert_eq!(count_words("hello — world …"), 2);
+    }
+}
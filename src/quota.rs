@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::http::HeaderMap;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+
+use crate::auth::ApiKey;
+use crate::models::{UsageResponse, UsageWindow};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Header Kong adds with the authenticated consumer's name.
+const CONSUMER_HEADER: &str = "x-consumer-username";
+const DEFAULT_TIER: &str = "default";
+/// Clients tracked at most; the one first counted longest ago is dropped.
+const MAX_CLIENTS: usize = 100_000;
+/// Counters from past days dropped per request.
+const EXPIRED_PER_CHECK: usize = 2;
+
+static QUOTAS: OnceCell<Quotas> = OnceCell::new();
+
+static USAGE: Lazy<Mutex<Usage>> = Lazy::new(|| Mutex::new(Usage::default()));
+
+// ── Error type ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaError {
+    #[error("cannot read quotas file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid quotas file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("client {client:?} uses unknown tier {tier:?}")]
+    UnknownTier { client: String, tier: String },
+}
+
+// ── Config file format ───────────────────────────────────────────────────────
+
+/// Request limits of one tier; a missing limit means unlimited.
+#[derive(Debug, Default, Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Tier {
+    pub per_minute: Option<u32>,
+    pub per_hour: Option<u32>,
+    pub per_day: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Quotas {
+    /// Tier for clients not listed in `clients`.
+    #[serde(default = "default_tier_name")]
+    pub default_tier: String,
+    #[serde(default)]
+    pub tiers: BTreeMap<String, Tier>,
+    /// Client id (as reported by `/usage`) to tier name.
+    #[serde(default)]
+    pub clients: BTreeMap<String, String>,
+    /// Identify callers by Kong's consumer header and `X-Forwarded-For`.
+    /// Only enable this when the service cannot be reached around the proxy.
+    #[serde(default)]
+    pub trust_proxy: bool,
+}
+
+fn default_tier_name() -> String {
+    DEFAULT_TIER.to_string()
+}
+
+impl Quotas {
+    fn default_config() -> Self {
+        Quotas {
+            default_tier: default_tier_name(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, QuotaError> {
+        let quotas: Quotas = serde_json::from_str(json)?;
+        let tiers = std::iter::once(("(default)", &quotas.default_tier))
+            .chain(quotas.clients.iter().map(|(c, t)| (c.as_str(), t)));
+        for (client, tier) in tiers {
+            if tier != DEFAULT_TIER && !quotas.tiers.contains_key(tier) {
+                return Err(QuotaError::UnknownTier {
+                    client: client.to_string(),
+                    tier: tier.clone(),
+                });
+            }
+        }
+        Ok(quotas)
+    }
+
+    fn tier_of<'a>(&'a self, client: &str) -> (&'a str, Tier) {
+        let name = self.clients.get(client).unwrap_or(&self.default_tier);
+        (name, self.tiers.get(name).copied().unwrap_or_default())
+    }
+}
+
+/// Load quotas from the file named by `JW_NEWS_READER_QUOTAS`, if set.
+/// Without one every client is in the unlimited `default` tier and usage is
+/// only counted.
+pub fn init_from_env() -> Result<(), QuotaError> {
+    let quotas = match std::env::var("JW_NEWS_READER_QUOTAS") {
+        Ok(path) if !path.is_empty() => {
+            let quotas = Quotas::from_json(&std::fs::read_to_string(&path)?)?;
+            tracing::info!("loaded {} quota tier(s) from {}", quotas.tiers.len(), path);
+            quotas
+        }
+        _ => Quotas::default_config(),
+    };
+    let _ = QUOTAS.set(quotas);
+    Ok(())
+}
+
+fn current() -> &'static Quotas {
+    QUOTAS.get_or_init(Quotas::default_config)
+}
+
+// ── Clients ──────────────────────────────────────────────────────────────────
+
+/// Identify the caller: `key:` plus the name of the API key that
+/// authenticated the request, else (with `trust_proxy`) `consumer:` plus
+/// Kong's consumer name or `ip:` plus the last `X-Forwarded-For` address,
+/// else `ip:` plus the peer address.
+pub fn client_id(key: Option<&ApiKey>, headers: &HeaderMap, peer: IpAddr) -> String {
+    current().client_id(key, headers, peer)
+}
+
+impl Quotas {
+    fn client_id(&self, key: Option<&ApiKey>, headers: &HeaderMap, peer: IpAddr) -> String {
+        if let Some(key) = key {
+            return format!("key:{}", key.name);
+        }
+        if self.trust_proxy {
+            if let Some(consumer) = header(headers, CONSUMER_HEADER) {
+                return format!("consumer:{}", consumer);
+            }
+            // The proxy appends the address it saw; earlier entries come from
+            // the client and can be forged.
+            let forwarded = header(headers, "x-forwarded-for")
+                .and_then(|v| v.rsplit(',').next())
+                .and_then(|v| v.trim().parse::<IpAddr>().ok());
+            if let Some(ip) = forwarded {
+                return format!("ip:{}", ip);
+            }
+        }
+        format!("ip:{}", peer)
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+// ── Counting ─────────────────────────────────────────────────────────────────
+
+/// Fixed windows aligned to the Unix epoch (UTC).
+const WINDOWS: [(&str, u64); 3] = [("minute", 60), ("hour", 3600), ("day", 86400)];
+
+/// Requests in the current minute, hour and day, with the window each count
+/// belongs to.
+#[derive(Debug, Default)]
+struct Counters([(u64, u32); 3]);
+
+impl Counters {
+    fn used(&self, now: u64) -> [u32; 3] {
+        let mut used = [0; 3];
+        for (i, (_, seconds)) in WINDOWS.iter().enumerate() {
+            let (window, count) = self.0[i];
+            if window == now / seconds {
+                used[i] = count;
+            }
+        }
+        used
+    }
+}
+
+fn limits(tier: Tier) -> [Option<u32>; 3] {
+    [tier.per_minute, tier.per_hour, tier.per_day]
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Counters of recently seen clients, with the day each client was first
+/// counted on, oldest first, so stale counters are dropped a few at a time.
+#[derive(Default)]
+struct Usage {
+    clients: HashMap<String, Counters>,
+    /// `(day window, client)`; entries whose client has since been counted
+    /// on a later day are skipped when popped.
+    days: VecDeque<(u64, String)>,
+}
+
+impl Usage {
+    fn used(&self, client: &str, now: u64) -> [u32; 3] {
+        self.clients
+            .get(client)
+            .map(|counters| counters.used(now))
+            .unwrap_or_default()
+    }
+
+    fn check(&mut self, client: &str, tier: Tier, now: u64) -> Result<(), u64> {
+        let today = now / WINDOWS[2].1;
+        for _ in 0..EXPIRED_PER_CHECK {
+            match self.days.front() {
+                Some((day, _)) if *day < today => self.pop_oldest(),
+                _ => break,
+            }
+        }
+
+        let used = self.used(client, now);
+        let retry_after = WINDOWS
+            .iter()
+            .zip(limits(tier))
+            .zip(used)
+            .filter(|((_, limit), used)| limit.is_some_and(|limit| *used >= limit))
+            .map(|(((_, seconds), _), _)| seconds - now % seconds)
+            .max();
+        if let Some(retry_after) = retry_after {
+            return Err(retry_after);
+        }
+
+        if !self.clients.contains_key(client) {
+            while self.clients.len() >= MAX_CLIENTS && !self.days.is_empty() {
+                self.pop_oldest();
+            }
+        }
+        let counters = self.clients.entry(client.to_string()).or_default();
+        if counters.0[2].0 != today {
+            self.days.push_back((today, client.to_string()));
+        }
+        for (i, (_, seconds)) in WINDOWS.iter().enumerate() {
+            counters.0[i] = (now / seconds, used[i] + 1);
+        }
+        Ok(())
+    }
+
+    /// Drop the oldest entry of `days`, and its client's counters unless they
+    /// were updated on a later day.
+    fn pop_oldest(&mut self) {
+        let Some((day, client)) = self.days.pop_front() else {
+            return;
+        };
+        if self
+            .clients
+            .get(&client)
+            .is_some_and(|counters| counters.0[2].0 == day)
+        {
+            self.clients.remove(&client);
+        }
+    }
+}
+
+/// Count a request from `client`. Fails with the seconds until the client
+/// may try again when a limit of its tier has been reached; rejected
+/// requests are not counted.
+pub fn check(client: &str) -> Result<(), u64> {
+    let (_, tier) = current().tier_of(client);
+    USAGE.lock().unwrap().check(client, tier, now())
+}
+
+/// Current usage and limits of `client`, for `/usage`.
+pub fn usage(client: &str) -> UsageResponse {
+    let (tier_name, tier) = current().tier_of(client);
+    let now = now();
+    let used = USAGE.lock().unwrap().used(client, now);
+
+    let windows = WINDOWS
+        .iter()
+        .zip(limits(tier))
+        .zip(used)
+        .map(|(((window, seconds), limit), used)| UsageWindow {
+            window,
+            limit,
+            used,
+            remaining: limit.map(|limit| limit.saturating_sub(used)),
+            reset_seconds: seconds - now % seconds,
+        })
+        .collect();
+    UsageResponse {
+        client: client.to_string(),
+        tier: tier_name.to_string(),
+        windows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00:30 UTC.
+    const T: u64 = 1_704_067_230;
+    const PEER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+
+    fn tier(per_minute: u32, per_hour: u32, per_day: u32) -> Tier {
+        Tier {
+            per_minute: Some(per_minute),
+            per_hour: Some(per_hour),
+            per_day: Some(per_day),
+        }
+    }
+
+    #[test]
+    fn minute_limit_resets_next_minute() {
+        let mut usage = Usage::default();
+        let tier = tier(2, 100, 1000);
+        assert_eq!(usage.check("a", tier, T), Ok(()));
+        assert_eq!(usage.check("a", tier, T + 1), Ok(()));
+        assert_eq!(usage.check("a", tier, T + 2), Err(28));
+        // Rejected requests are not counted.
+        assert_eq!(usage.used("a", T + 2), [2, 2, 2]);
+        assert_eq!(usage.check("a", tier, T + 30), Ok(()));
+        assert_eq!(usage.used("a", T + 30), [1, 3, 3]);
+    }
+
+    #[test]
+    fn longest_exceeded_window_sets_retry_after() {
+        let mut usage = Usage::default();
+        let tier = tier(1, 1, 1000);
+        assert_eq!(usage.check("a", tier, T), Ok(()));
+        assert_eq!(usage.check("a", tier, T + 1), Err(3600 - 31));
+        assert_eq!(usage.check("a", tier, T + 3600), Ok(()));
+    }
+
+    #[test]
+    fn clients_are_counted_separately() {
+        let mut usage = Usage::default();
+        let tier = tier(1, 10, 10);
+        assert_eq!(usage.check("a", tier, T), Ok(()));
+        assert_eq!(usage.check("b", tier, T), Ok(()));
+        assert!(usage.check("a", tier, T).is_err());
+        assert!(usage.check("c", Tier::default(), T).is_ok());
+    }
+
+    #[test]
+    fn drops_counters_from_past_days() {
+        let mut usage = Usage::default();
+        let tier = Tier::default();
+        for client in ["a", "b", "c"] {
+            usage.check(client, tier, T).unwrap();
+        }
+        // "a" is counted again the next day; "b" and "c" expire.
+        usage.check("a", tier, T + 86400).unwrap();
+        usage.check("a", tier, T + 86401).unwrap();
+        assert!(usage.clients.contains_key("a"));
+        assert!(!usage.clients.contains_key("b"));
+        assert!(!usage.clients.contains_key("c"));
+        assert_eq!(usage.used("a", T + 86401), [2, 2, 2]);
+        assert_eq!(usage.days.len(), 1);
+    }
+
+    #[test]
+    fn client_id_prefers_authenticated_key() {
+        let quotas = Quotas {
+            trust_proxy: true,
+            ..Quotas::default_config()
+        };
+        let key = ApiKey {
+            name: "reader-app".to_string(),
+            scopes: Vec::new(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("apikey", "forged".parse().unwrap());
+        headers.insert(CONSUMER_HEADER, "partner".parse().unwrap());
+        assert_eq!(
+            quotas.client_id(Some(&key), &headers, PEER),
+            "key:reader-app"
+        );
+        assert_eq!(quotas.client_id(None, &headers, PEER), "consumer:partner");
+    }
+
+    #[test]
+    fn client_id_uses_last_forwarded_address_behind_proxy() {
+        let mut headers = HeaderMap::new();
+        headers.insert("apikey", "unchecked".parse().unwrap());
+        headers.insert("x-forwarded-for", "10.9.9.9, 203.0.113.7".parse().unwrap());
+        let direct = Quotas::default_config();
+        assert_eq!(direct.client_id(None, &headers, PEER), "ip:192.0.2.1");
+        let proxied = Quotas {
+            trust_proxy: true,
+            ..Quotas::default_config()
+        };
+        assert_eq!(proxied.client_id(None, &headers, PEER), "ip:203.0.113.7");
+    }
+}
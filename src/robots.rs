@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use url::Url;
+
+use crate::extract::{self, ExtractionError};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Product token matched against `User-agent` lines.
+const AGENT_TOKEN: &str = "jw-news-reader-api";
+/// How long a fetched robots.txt is trusted.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// How long a failed robots.txt fetch is remembered (as "allow all").
+const ERROR_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// robots.txt checks are opt-in (`JW_NEWS_READER_ROBOTS=1`).
+static ENABLED: Lazy<bool> =
+    Lazy::new(|| std::env::var("JW_NEWS_READER_ROBOTS").as_deref() == Ok("1"));
+
+static CACHE: Lazy<Mutex<HashMap<String, Cached>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Cached {
+    fetched: Instant,
+    ttl: Duration,
+    robots: Robots,
+}
+
+// ── Rules ────────────────────────────────────────────────────────────────────
+
+/// The `Allow`/`Disallow` rules that apply to this service.
+#[derive(Debug, Clone, Default)]
+struct Robots {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    allow: bool,
+    /// Length of the rule as written; the longest match wins.
+    len: usize,
+    pattern: Regex,
+}
+
+impl Robots {
+    /// Rules of the group naming our agent token, else of the `*` group.
+    fn parse(text: &str) -> Robots {
+        let mut specific = Vec::new();
+        let mut wildcard = Vec::new();
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        let mut named = false;
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (field, value) = (field.trim().to_lowercase(), value.trim());
+            match field.as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group.
+                    if in_rules {
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    let agent = value.to_lowercase();
+                    named |= is_ours(&agent);
+                    agents.push(agent);
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    let Some(rule) = Rule::new(field == "allow", value) else {
+                        continue;
+                    };
+                    if agents.iter().any(|a| is_ours(a)) {
+                        specific.push(rule.clone());
+                    }
+                    if agents.iter().any(|a| a == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Robots {
+            rules: if named { specific } else { wildcard },
+        }
+    }
+
+    /// Longest matching rule wins; `Allow` wins ties. No match allows.
+    fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(path))
+            .max_by_key(|rule| (rule.len, rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+/// A `User-agent` value naming this service: its product token, ignoring
+/// case and any `/version`.
+fn is_ours(agent: &str) -> bool {
+    let token = agent.split('/').next().unwrap_or("").trim();
+    token.eq_ignore_ascii_case(AGENT_TOKEN)
+}
+
+impl Rule {
+    /// Compile a path pattern: a prefix match where `*` matches any run of
+    /// characters and a trailing `$` anchors the end. Empty patterns match
+    /// nothing.
+    fn new(allow: bool, value: &str) -> Option<Rule> {
+        if value.is_empty() {
+            return None;
+        }
+        let (body, anchored) = match value.strip_suffix('$') {
+            Some(body) => (body, true),
+            None => (value, false),
+        };
+        let parts: Vec<String> = body.split('*').map(regex::escape).collect();
+        let pattern = format!("^{}{}", parts.join(".*"), if anchored { "$" } else { "" });
+        Some(Rule {
+            allow,
+            len: value.len(),
+            pattern: Regex::new(&pattern).ok()?,
+        })
+    }
+}
+
+// ── Checking ─────────────────────────────────────────────────────────────────
+
+/// Fail with `DisallowedByRobots` when robots checks are enabled and the
+/// host's robots.txt disallows `url`.
+pub async fn check(url: &Url) -> Result<(), ExtractionError> {
+    if !*ENABLED {
+        return Ok(());
+    }
+    let Some(host) = url.host_str() else {
+        return Ok(());
+    };
+    let origin = format!("{}://{}", url.scheme(), host);
+
+    let cached = CACHE
+        .lock()
+        .unwrap()
+        .get(&origin)
+        .filter(|cached| cached.fetched.elapsed() < cached.ttl)
+        .map(|cached| cached.robots.clone());
+    let robots = match cached {
+        Some(robots) => robots,
+        None => {
+            let (robots, ttl) = match extract::fetch_robots(&format!("{}/robots.txt", origin)).await
+            {
+                Ok(Some(text)) => (Robots::parse(&text), CACHE_TTL),
+                Ok(None) => (Robots::default(), CACHE_TTL),
+                Err(e) => {
+                    tracing::warn!("could not fetch robots.txt for {}: {}", origin, e);
+                    (Robots::default(), ERROR_TTL)
+                }
+            };
+            let cached = Cached {
+                fetched: Instant::now(),
+                ttl,
+                robots: robots.clone(),
+            };
+            CACHE.lock().unwrap().insert(origin, cached);
+            robots
+        }
+    };
+
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    if robots.allows(&path) {
+        Ok(())
+    } else {
+        Err(ExtractionError::DisallowedByRobots(url.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_whole_agent_token() {
+        assert!(is_ours("jw-news-reader-api"));
+        assert!(is_ours("JW-News-Reader-API"));
+        assert!(is_ours("jw-news-reader-api/1.0"));
+        assert!(!is_ours("j"));
+        assert!(!is_ours("jw"));
+        assert!(!is_ours("jw-news-reader-api-beta"));
+        assert!(!is_ours("*"));
+        assert!(!is_ours(""));
+    }
+
+    #[test]
+    fn prefers_named_group_over_wildcard() {
+        let robots = Robots::parse(
+            "User-agent: *\n\
+             Disallow: /\n\
+             \n\
+             User-agent: Googlebot\n\
+             User-Agent: JW-News-Reader-API # us\n\
+             Disallow: /private/\n",
+        );
+        assert!(robots.allows("/en/news/"));
+        assert!(!robots.allows("/private/page"));
+    }
+
+    #[test]
+    fn falls_back_to_wildcard_group() {
+        let robots = Robots::parse(
+            "User-agent: jw\n\
+             Disallow: /\n\
+             \n\
+             User-agent: *\n\
+             Disallow: /search\n",
+        );
+        assert!(robots.allows("/en/news/"));
+        assert!(!robots.allows("/search?q=x"));
+    }
+
+    #[test]
+    fn longest_match_wins_and_allow_wins_ties() {
+        let robots = Robots::parse(
+            "User-agent: *\n\
+             Disallow: /en/\n\
+             Allow: /en/news/\n\
+             Disallow: /en/news/archive\n\
+             Allow: /same\n\
+             Disallow: /same\n",
+        );
+        assert!(!robots.allows("/en/library/"));
+        assert!(robots.allows("/en/news/today"));
+        assert!(!robots.allows("/en/news/archive/2020"));
+        assert!(robots.allows("/same/page"));
+        assert!(robots.allows("/fr/"));
+    }
+
+    #[test]
+    fn supports_wildcards_and_end_anchor() {
+        let robots = Robots::parse(
+            "User-agent: *\n\
+             Disallow: /*.pdf$\n\
+             Disallow: /*/print/\n\
+             Disallow:\n",
+        );
+        assert!(!robots.allows("/en/doc.pdf"));
+        assert!(robots.allows("/en/doc.pdf?download=1"));
+        assert!(!robots.allows("/en/print/article"));
+        assert!(robots.allows("/en/article"));
+    }
+
+    #[test]
+    fn empty_file_allows_everything() {
+        assert!(Robots::parse("").allows("/anything"));
+    }
+}
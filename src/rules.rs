@@ -0,0 +1,317 @@
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use scraper::Selector;
+use serde::Deserialize;
+use url::Url;
+
+use crate::wol;
+
+// ── Defaults ─────────────────────────────────────────────────────────────────
+
+const DEFAULT_KEYWORD_PATTERN: &str = r"(?i)(article|content|pub|body)";
+const DEFAULT_PLAYER_CLASS_PATTERN: &str = r"(?i)(player|audio|video|jwplayer|vjs|media|play)";
+const DEFAULT_METADATA_CLASS_PATTERN: &str =
+    r"(?i)(publication|issue|magazine|context|related|footer|language|promo|share)";
+const DEFAULT_ISSUE_PATTERN: &str = r"(?i)\bwp\d{2}\b";
+
+static RULES: OnceCell<RuleSet> = OnceCell::new();
+
+// ── Error type ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, thiserror::Error)]
+pub enum RulesError {
+    #[error("cannot read rules file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid rules file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("rule {rule:?}: invalid selector {selector:?}")]
+    Selector { rule: String, selector: String },
+    #[error("rule {rule:?}: invalid pattern: {source}")]
+    Pattern { rule: String, source: regex::Error },
+}
+
+// ── Config file format ───────────────────────────────────────────────────────
+
+/// Extra, layout-specific processing applied on top of the generic walker.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    #[default]
+    Standard,
+    /// Watchtower Online Library documents (see `wol.rs`).
+    Wol,
+}
+
+/// One entry of the rules file. Every field is optional; anything left out
+/// falls back to the built-in jw.org heuristics.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConfig {
+    pub name: Option<String>,
+    /// Exact host (`wol.jw.org`) or wildcard suffix (`*.jw.org`).
+    pub host: Option<String>,
+    pub path_prefix: Option<String>,
+    pub profile: Option<Profile>,
+    #[serde(default)]
+    pub container_selectors: Vec<String>,
+    #[serde(default)]
+    pub remove_selectors: Vec<String>,
+    #[serde(default)]
+    pub title_selectors: Vec<String>,
+    pub keyword_pattern: Option<String>,
+    pub player_class_pattern: Option<String>,
+    pub metadata_class_pattern: Option<String>,
+    pub issue_pattern: Option<String>,
+    /// Extra publication names and issue markers, on top of the built-in
+    /// table for the article's language (see `lang.rs`).
+    #[serde(default)]
+    pub publication_names: Vec<String>,
+    #[serde(default)]
+    pub issue_markers: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RulesFile {
+    #[serde(default)]
+    pub rules: Vec<RuleConfig>,
+}
+
+// ── Compiled rules ───────────────────────────────────────────────────────────
+
+/// Extraction heuristics for one site section, ready to apply.
+#[derive(Debug)]
+pub struct SiteRules {
+    pub name: String,
+    host: Option<String>,
+    path_prefix: Option<String>,
+    pub profile: Profile,
+    pub container_selectors: Vec<Selector>,
+    pub remove_selectors: Vec<Selector>,
+    pub title_selectors: Vec<Selector>,
+    pub keyword_re: Regex,
+    pub player_class_re: Regex,
+    pub metadata_class_re: Regex,
+    pub issue_re: Regex,
+    pub publication_names: Vec<String>,
+    pub issue_markers: Vec<String>,
+}
+
+impl SiteRules {
+    pub fn compile(config: RuleConfig) -> Result<Self, RulesError> {
+        let name = config.name.unwrap_or_else(|| "default".to_string());
+
+        let selectors = |list: Vec<String>| -> Result<Vec<Selector>, RulesError> {
+            list.into_iter()
+                .map(|s| {
+                    Selector::parse(&s).map_err(|_| RulesError::Selector {
+                        rule: name.clone(),
+                        selector: s.clone(),
+                    })
+                })
+                .collect()
+        };
+        let pattern = |p: Option<String>, default: &str| -> Result<Regex, RulesError> {
+            Regex::new(p.as_deref().unwrap_or(default)).map_err(|source| RulesError::Pattern {
+                rule: name.clone(),
+                source,
+            })
+        };
+
+        Ok(SiteRules {
+            container_selectors: selectors(config.container_selectors)?,
+            remove_selectors: selectors(config.remove_selectors)?,
+            title_selectors: selectors(config.title_selectors)?,
+            keyword_re: pattern(config.keyword_pattern, DEFAULT_KEYWORD_PATTERN)?,
+            player_class_re: pattern(config.player_class_pattern, DEFAULT_PLAYER_CLASS_PATTERN)?,
+            metadata_class_re: pattern(
+                config.metadata_class_pattern,
+                DEFAULT_METADATA_CLASS_PATTERN,
+            )?,
+            issue_re: pattern(config.issue_pattern, DEFAULT_ISSUE_PATTERN)?,
+            publication_names: config.publication_names,
+            issue_markers: config.issue_markers,
+            host: config.host.map(|h| h.to_lowercase()),
+            path_prefix: config.path_prefix,
+            profile: config.profile.unwrap_or_default(),
+            name,
+        })
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or("").to_lowercase();
+        let host_ok = match self.host.as_deref() {
+            None => true,
+            Some(pattern) => match pattern.strip_prefix("*.") {
+                Some(suffix) => host == suffix || host.ends_with(&format!(".{}", suffix)),
+                None => host == pattern,
+            },
+        };
+        let path_ok = self
+            .path_prefix
+            .as_deref()
+            .map(|p| url.path().starts_with(p))
+            .unwrap_or(true);
+        host_ok && path_ok
+    }
+}
+
+/// All configured rules plus the built-in ones. Configured rules are tried
+/// in file order, then the built-in site profiles, then the defaults; the
+/// first one matching the URL wins.
+#[derive(Debug)]
+pub struct RuleSet {
+    rules: Vec<SiteRules>,
+    builtin: Vec<SiteRules>,
+    default: SiteRules,
+}
+
+impl RuleSet {
+    pub fn builtin() -> Self {
+        let compile =
+            |config| SiteRules::compile(config).expect("built-in extraction rules are valid");
+        RuleSet {
+            rules: Vec::new(),
+            builtin: vec![compile(wol::rule_config())],
+            default: compile(RuleConfig::default()),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, RulesError> {
+        let file: RulesFile = serde_json::from_str(json)?;
+        let rules = file
+            .rules
+            .into_iter()
+            .map(SiteRules::compile)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RuleSet {
+            rules,
+            ..RuleSet::builtin()
+        })
+    }
+
+    pub fn for_url(&self, url: &Url) -> &SiteRules {
+        self.rules
+            .iter()
+            .chain(&self.builtin)
+            .find(|r| r.matches(url))
+            .unwrap_or(&self.default)
+    }
+}
+
+// ── Global rule set ──────────────────────────────────────────────────────────
+
+/// Load rules from the file named by `JW_NEWS_READER_RULES`, if set, and
+/// install them for the lifetime of the process.
+pub fn init_from_env() -> Result<(), RulesError> {
+    let set = match std::env::var("JW_NEWS_READER_RULES") {
+        Ok(path) if !path.is_empty() => {
+            let set = RuleSet::from_json(&std::fs::read_to_string(&path)?)?;
+            tracing::info!(
+                "loaded {} extraction rule(s) from {}",
+                set.rules.len(),
+                path
+            );
+            set
+        }
+        _ => RuleSet::builtin(),
+    };
+    // A second call keeps the rules that were installed first.
+    let _ = RULES.set(set);
+    Ok(())
+}
+
+pub fn current() -> &'static RuleSet {
+    RULES.get_or_init(RuleSet::builtin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn rule(host: Option<&str>, path_prefix: Option<&str>) -> SiteRules {
+        SiteRules::compile(RuleConfig {
+            host: host.map(str::to_string),
+            path_prefix: path_prefix.map(str::to_string),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn matches_exact_and_wildcard_hosts() {
+        let exact = rule(Some("WWW.jw.org"), None);
+        assert!(exact.matches(&url("https://www.jw.org/en/")));
+        assert!(!exact.matches(&url("https://wol.jw.org/en/")));
+
+        let wildcard = rule(Some("*.jw.org"), None);
+        assert!(wildcard.matches(&url("https://jw.org/en/")));
+        assert!(wildcard.matches(&url("https://wol.jw.org/en/")));
+        assert!(!wildcard.matches(&url("https://notjw.org/en/")));
+        assert!(!wildcard.matches(&url("https://jw.org.example.com/")));
+
+        assert!(rule(None, None).matches(&url("https://example.com/")));
+    }
+
+    #[test]
+    fn matches_path_prefix() {
+        let news = rule(Some("www.jw.org"), Some("/en/news/"));
+        assert!(news.matches(&url("https://www.jw.org/en/news/region/")));
+        assert!(!news.matches(&url("https://www.jw.org/en/library/")));
+        assert!(!news.matches(&url("https://www.jw.org/es/news/")));
+    }
+
+    #[test]
+    fn file_rules_come_before_builtin_ones() {
+        let set = RuleSet::from_json(
+            r#"{ "rules": [
+                { "name": "wol-lfb", "host": "wol.jw.org", "path_prefix": "/en/wol/d/r1/lp-e/1102016" },
+                { "name": "news", "host": "*.jw.org", "path_prefix": "/en/news/" },
+                { "name": "jw", "host": "*.jw.org" }
+            ] }"#,
+        )
+        .unwrap();
+        let name = |u: &str| set.for_url(&url(u)).name.as_str();
+        assert_eq!(
+            name("https://wol.jw.org/en/wol/d/r1/lp-e/1102016001"),
+            "wol-lfb"
+        );
+        assert_eq!(name("https://www.jw.org/en/news/region/"), "news");
+        // The catch-all file rule shadows the built-in WOL profile.
+        assert_eq!(name("https://wol.jw.org/en/wol/d/r1/lp-e/2020"), "jw");
+        assert_eq!(name("https://example.com/"), "default");
+    }
+
+    #[test]
+    fn builtin_rules_without_a_file() {
+        let set = RuleSet::builtin();
+        let wol = set.for_url(&url("https://wol.jw.org/en/wol/d/r1/lp-e/2020"));
+        assert_eq!(wol.name, "wol");
+        assert_eq!(wol.profile, Profile::Wol);
+        let other = set.for_url(&url("https://www.jw.org/en/"));
+        assert_eq!(other.name, "default");
+        assert_eq!(other.profile, Profile::Standard);
+    }
+
+    #[test]
+    fn rejects_invalid_selectors_and_patterns() {
+        let err = RuleSet::from_json(
+            r#"{ "rules": [ { "name": "bad", "container_selectors": ["div[["] } ] }"#,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            RulesError::Selector { ref rule, ref selector } if rule == "bad" && selector == "div[["
+        ));
+
+        let err = RuleSet::from_json(r#"{ "rules": [ { "issue_pattern": "(" } ] }"#).unwrap_err();
+        assert!(matches!(err, RulesError::Pattern { ref rule, .. } if rule == "default"));
+
+        let err = RuleSet::from_json(r#"{ "rules": [ { "hots": "jw.org" } ] }"#).unwrap_err();
+        assert!(matches!(err, RulesError::Json(_)));
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::extract::{collect_text, normalize_text};
+use crate::rules::SiteRules;
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Paragraphs shorter than this are ignored when scoring.
+const MIN_PARAGRAPH_LEN: usize = 25;
+/// Bonus/penalty applied when an element's class/id looks like content/chrome.
+const CLASS_WEIGHT: f64 = 25.0;
+/// Minimum score a sibling needs to be merged with the top candidate.
+const MIN_SIBLING_SCORE: f64 = 10.0;
+
+// ── Result type ──────────────────────────────────────────────────────────────
+
+/// The winning block, possibly merged with related siblings, in document order.
+pub struct Candidate<'doc> {
+    pub elements: Vec<ElementRef<'doc>>,
+    pub score: f64,
+}
+
+// ── Scoring ──────────────────────────────────────────────────────────────────
+
+/// Readability-style content scoring: every paragraph awards points to its
+/// ancestors based on its length and comma count, ancestors are weighted by
+/// tag and class, and the final score is discounted by link density.
+pub fn best_candidate<'doc>(document: &'doc Html, rules: &SiteRules) -> Option<Candidate<'doc>> {
+    let body_sel = Selector::parse("body").unwrap();
+    let body = document.select(&body_sel).next()?;
+
+    let mut scores: HashMap<NodeId, (ElementRef<'doc>, f64)> = HashMap::new();
+
+    for el in body.descendants().filter_map(ElementRef::wrap) {
+        if !matches!(el.value().name(), "p" | "pre" | "td") || is_unlikely(el) {
+            continue;
+        }
+        let text = normalize_text(collect_text(el));
+        if text.len() < MIN_PARAGRAPH_LEN {
+            continue;
+        }
+
+        let content_score =
+            1.0 + text.matches(',').count() as f64 + (text.len() as f64 / 100.0).min(3.0);
+
+        let ancestors = el.ancestors().filter_map(ElementRef::wrap).take(3);
+        for (level, ancestor) in ancestors.enumerate() {
+            if matches!(ancestor.value().name(), "html") {
+                break;
+            }
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                n => n as f64 * 3.0,
+            };
+            let entry = scores
+                .entry(ancestor.id())
+                .or_insert_with(|| (ancestor, initial_score(ancestor, rules)));
+            entry.1 += content_score / divider;
+        }
+    }
+
+    let final_score = |el: ElementRef<'_>, raw: f64| raw * (1.0 - link_density(el));
+
+    let (top, top_score) = scores
+        .values()
+        .map(|(el, raw)| (*el, final_score(*el, *raw)))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))?;
+
+    // Merge siblings that look like part of the same article (e.g. the body
+    // split across several blocks under a common parent).
+    let threshold = MIN_SIBLING_SCORE.max(top_score * 0.2);
+    let top_classes = top.value().attr("class").unwrap_or("");
+    let mut elements = Vec::new();
+
+    let siblings = match top.parent().and_then(ElementRef::wrap) {
+        Some(parent) => parent.children().filter_map(ElementRef::wrap).collect(),
+        None => vec![top],
+    };
+    for sibling in siblings {
+        if sibling.id() == top.id() {
+            elements.push(sibling);
+            continue;
+        }
+
+        let mut bonus = 0.0;
+        if !top_classes.is_empty() && sibling.value().attr("class") == Some(top_classes) {
+            bonus += top_score * 0.2;
+        }
+        let sibling_score = scores
+            .get(&sibling.id())
+            .map(|(el, raw)| final_score(*el, *raw))
+            .unwrap_or(0.0);
+        if sibling_score + bonus >= threshold {
+            elements.push(sibling);
+            continue;
+        }
+
+        if sibling.value().name() == "p" {
+            let text = normalize_text(collect_text(sibling));
+            let density = link_density(sibling);
+            if (text.len() > 80 && density < 0.25) || (density == 0.0 && text.contains(". ")) {
+                elements.push(sibling);
+            }
+        }
+    }
+
+    Some(Candidate {
+        elements,
+        score: (top_score * 100.0).round() / 100.0,
+    })
+}
+
+fn initial_score(el: ElementRef<'_>, rules: &SiteRules) -> f64 {
+    let base = match el.value().name() {
+        "div" | "article" | "main" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    base + class_weight(el, rules)
+}
+
+fn class_weight(el: ElementRef<'_>, rules: &SiteRules) -> f64 {
+    let mut weight = 0.0;
+    for value in [el.value().id(), el.value().attr("class")]
+        .into_iter()
+        .flatten()
+    {
+        if rules.metadata_class_re.is_match(value) || rules.player_class_re.is_match(value) {
+            weight -= CLASS_WEIGHT;
+        }
+        if rules.keyword_re.is_match(value) {
+            weight += CLASS_WEIGHT;
+        }
+    }
+    weight
+}
+
+/// Share of an element's text that sits inside links.
+fn link_density(el: ElementRef<'_>) -> f64 {
+    let total = normalize_text(collect_text(el)).len();
+    if total == 0 {
+        return 0.0;
+    }
+    let linked: usize = el
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|d| d.value().name() == "a")
+        .map(|a| normalize_text(collect_text(a)).len())
+        .sum();
+    (linked as f64 / total as f64).min(1.0)
+}
+
+/// Paragraphs inside navigation chrome never count towards a candidate.
+fn is_unlikely(el: ElementRef<'_>) -> bool {
+    el.ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|a| matches!(a.value().name(), "nav" | "footer" | "aside" | "form"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{RuleConfig, SiteRules};
+
+    /// Ids of the chosen elements.
+    fn chosen(html: &str) -> Vec<String> {
+        let document = Html::parse_document(html);
+        let rules = SiteRules::compile(RuleConfig::default()).unwrap();
+        best_candidate(&document, &rules)
+            .unwrap()
+            .elements
+            .iter()
+            .map(|el| el.value().id().unwrap_or("?").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn article_body_beats_main_full_of_chrome() {
+        let html = include_str!("../tests/fixtures/scoring/main-chrome.html");
+        assert_eq!(chosen(html), ["story"]);
+    }
+
+    #[test]
+    fn picks_full_article_among_teasers() {
+        let html = include_str!("../tests/fixtures/scoring/several-articles.html");
+        assert_eq!(chosen(html), ["full"]);
+    }
+
+    #[test]
+    fn merges_split_siblings_but_not_promos() {
+        let html = include_str!("../tests/fixtures/scoring/split.html");
+        assert_eq!(chosen(html), ["part-1", "part-2"]);
+    }
+
+    #[test]
+    fn penalises_link_dense_blocks() {
+        let html = include_str!("../tests/fixtures/scoring/link-dense.html");
+        assert_eq!(chosen(html), ["text"]);
+
+        let document = Html::parse_document(html);
+        let related = document
+            .select(&Selector::parse("#related").unwrap())
+            .next()
+            .unwrap();
+        assert!(link_density(related) > 0.9);
+    }
+}
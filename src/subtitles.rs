@@ -0,0 +1,226 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tokio::task::JoinSet;
+
+use crate::extract;
+use crate::models::{MediaInfo, SubtitleCue, TrackText};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Upper bound on subtitle tracks downloaded for one article.
+const MAX_TRACKS: usize = 10;
+/// Silence (in seconds) between cues that starts a new transcript paragraph.
+const PARAGRAPH_GAP: f64 = 2.0;
+
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+static TIMESTAMP_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:(\d+):)?(\d{2}):(\d{2})[.,](\d{3})$").unwrap());
+
+// ── Download ─────────────────────────────────────────────────────────────────
+
+/// Download the subtitle and caption tracks of `media` and attach their cues
+/// and transcript. Failures are recorded on the track instead of failing the
+/// extraction.
+pub async fn fetch_tracks(media: &mut [MediaInfo]) {
+    let mut tasks = JoinSet::new();
+    let positions = media
+        .iter()
+        .enumerate()
+        .flat_map(|(m, item)| {
+            item.tracks
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| is_text_track(t.kind.as_deref()))
+                .map(move |(t, _)| (m, t))
+        })
+        .take(MAX_TRACKS);
+    for (m, t) in positions {
+        let url = media[m].tracks[t].url.clone();
+        tasks.spawn(async move {
+            let text = match extract::fetch_asset_text(&url).await {
+                Ok(body) => parse(&body),
+                Err(e) => Err(e.to_string()),
+            };
+            (m, t, text)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((m, t, text)) = joined else { continue };
+        media[m].tracks[t].text = Some(text.unwrap_or_else(|error| TrackText {
+            error: Some(error),
+            ..TrackText::default()
+        }));
+    }
+}
+
+/// Tracks without a `kind` default to subtitles, as in HTML.
+fn is_text_track(kind: Option<&str>) -> bool {
+    matches!(kind, None | Some("subtitles") | Some("captions"))
+}
+
+// ── WebVTT ───────────────────────────────────────────────────────────────────
+
+/// Parse a WebVTT file into cues and a transcript.
+pub fn parse(body: &str) -> Result<TrackText, String> {
+    let cues = parse_vtt(body)?;
+    Ok(TrackText {
+        transcript: transcript(&cues),
+        cues,
+        error: None,
+    })
+}
+
+/// Parse the cues of a WebVTT file. `NOTE`, `STYLE` and `REGION` blocks and
+/// cue settings are ignored; markup is stripped from the cue text.
+fn parse_vtt(body: &str) -> Result<Vec<SubtitleCue>, String> {
+    let body = body.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut blocks = body.split("\n\n").map(str::trim).filter(|b| !b.is_empty());
+
+    let header = blocks.next().unwrap_or("");
+    if header != "WEBVTT" && !header.starts_with("WEBVTT ") && !header.starts_with("WEBVTT\n") {
+        return Err("Not a WebVTT file".to_string());
+    }
+
+    let mut cues = Vec::new();
+    for block in blocks {
+        let mut lines = block.lines();
+        let Some(mut timing) = lines.next() else {
+            continue;
+        };
+        if !timing.contains("-->") {
+            // Optional cue identifier, or a NOTE/STYLE/REGION block.
+            match lines.next() {
+                Some(next) if next.contains("-->") => timing = next,
+                _ => continue,
+            }
+        }
+
+        let Some((start, end)) = timing.split_once("-->") else {
+            continue;
+        };
+        let end = end.split_whitespace().next().unwrap_or("");
+        let (Some(start), Some(end)) = (parse_timestamp(start.trim()), parse_timestamp(end))
+        else {
+            continue;
+        };
+
+        let text = lines
+            .map(cue_line)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.is_empty() {
+            cues.push(SubtitleCue { start, end, text });
+        }
+    }
+    Ok(cues)
+}
+
+/// `hh:mm:ss.ttt` or `mm:ss.ttt` in seconds.
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let cap = TIMESTAMP_RE.captures(value)?;
+    let hours: f64 = cap.get(1).map_or(Ok(0.0), |h| h.as_str().parse()).ok()?;
+    let minutes: f64 = cap[2].parse().ok()?;
+    let seconds: f64 = cap[3].parse().ok()?;
+    let millis: f64 = cap[4].parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds + millis / 1000.0)
+}
+
+/// Strip cue markup (`<v Speaker>`, `<i>`, inline timestamps) and decode the
+/// character references WebVTT allows.
+fn cue_line(line: &str) -> String {
+    let text = TAG_RE.replace_all(line, "");
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lrm;", "")
+        .replace("&rlm;", "")
+        .replace("&amp;", "&");
+    extract::normalize_text(text)
+}
+
+/// Join cue text into paragraphs, breaking on pauses and dropping lines that
+/// repeat the previous cue (roll-up captions).
+fn transcript(cues: &[SubtitleCue]) -> String {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut last_line = "";
+    let mut last_end: Option<f64> = None;
+
+    for cue in cues {
+        if last_end.is_some_and(|end| cue.start - end > PARAGRAPH_GAP) && !current.is_empty() {
+            paragraphs.push(std::mem::take(&mut current));
+        }
+        for line in cue.text.lines() {
+            if line == last_line {
+                continue;
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(line);
+            last_line = line;
+        }
+        last_end = Some(cue.end);
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    paragraphs.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cues_and_skips_metadata_blocks() {
+        let text = parse(include_str!("../tests/fixtures/subtitles/health.vtt")).unwrap();
+        let cues = &text.cues;
+        assert_eq!(cues.len(), 4);
+
+        assert_eq!(cues[0].start, 1.0);
+        assert_eq!(cues[0].end, 3.5);
+        assert_eq!(cues[0].text, "Good health is a gift.");
+        assert_eq!(cues[1].text, "We can take care of it\nin simple ways.");
+        // `mm:ss.ttt` timestamps and character references.
+        assert_eq!(cues[2].start, 7.0);
+        assert_eq!(cues[2].end, 9.125);
+        assert_eq!(cues[2].text, "Rest, food & exercise <all> help.");
+        assert_eq!(cues[3].start, 3610.0);
+    }
+
+    #[test]
+    fn builds_paragraphs_from_pauses() {
+        let text = parse(include_str!("../tests/fixtures/subtitles/health.vtt")).unwrap();
+        assert_eq!(
+            text.transcript,
+            "Good health is a gift. We can take care of it in simple ways. \
+             Rest, food & exercise <all> help.\n\nThe end."
+        );
+    }
+
+    #[test]
+    fn drops_repeated_roll_up_lines() {
+        let text = parse(include_str!("../tests/fixtures/subtitles/rollup.vtt")).unwrap();
+        assert_eq!(text.cues.len(), 3);
+        assert_eq!(text.transcript, "Welcome to our program. Let's begin.");
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert!(parse(include_str!("../tests/fixtures/subtitles/not-vtt.srt")).is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("00:01.500"), Some(1.5));
+        assert_eq!(parse_timestamp("02:03:04.005"), Some(7384.005));
+        assert_eq!(parse_timestamp("1.5"), None);
+    }
+}
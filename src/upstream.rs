@@ -0,0 +1,298 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use once_cell::sync::Lazy;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+
+use crate::extract::ExtractionError;
+use crate::models::{CircuitState, CircuitStatus};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Attempts per fetch, including the first one.
+pub const MAX_ATTEMPTS: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(4);
+/// Longer `Retry-After` values fail the fetch instead of waiting.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Consecutive failed fetches that open a host's circuit.
+const FAILURE_THRESHOLD: u32 = 5;
+/// How long an open circuit fails fast before letting a trial request through.
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+
+static CIRCUITS: Lazy<Mutex<HashMap<String, Circuit>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// ── Retries ──────────────────────────────────────────────────────────────────
+
+/// Statuses worth retrying: rate limiting and gateway/availability errors.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Parse `Retry-After` as delay-seconds or an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Delay before retry number `attempt` (1-based): exponential backoff with
+/// full jitter, but never shorter than the server's `Retry-After`. Returns
+/// `None` when the server asks to wait longer than `MAX_RETRY_AFTER`.
+pub fn backoff(attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+    if retry_after.is_some_and(|d| d > MAX_RETRY_AFTER) {
+        return None;
+    }
+    let ceiling = BASE_BACKOFF
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(MAX_BACKOFF);
+    let jittered = ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0));
+    Some(jittered.max(retry_after.unwrap_or(Duration::ZERO)))
+}
+
+// ── Circuit breaker ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// A trial request is in flight while half-open.
+    trial: bool,
+}
+
+/// Permission to fetch from a host, from [`check`]. Report the outcome with
+/// [`Attempt::succeeded`] or [`Attempt::failed`]; dropping it unreported
+/// (the fetch gave up before reaching the host, or was cancelled) frees a
+/// pending trial slot without changing the circuit.
+pub struct Attempt {
+    host: String,
+    /// This attempt is the half-open circuit's trial request.
+    trial: bool,
+}
+
+impl Attempt {
+    /// The host answered; close its circuit.
+    pub fn succeeded(mut self) {
+        self.trial = false;
+        let mut circuits = CIRCUITS.lock().unwrap();
+        if let Some(circuit) = circuits.get_mut(&self.host) {
+            if circuit.opened_at.is_some() {
+                tracing::info!("circuit for {} closed", self.host);
+            }
+            *circuit = Circuit::default();
+        }
+    }
+
+    /// Report the status the host finally answered with: any `5xx` is a
+    /// failure, whether or not it was retried, and anything else a success.
+    pub fn answered(self, status: StatusCode) {
+        if status.is_server_error() {
+            self.failed();
+        } else {
+            self.succeeded();
+        }
+    }
+
+    /// The host failed after all retries; open its circuit once the
+    /// failures reach `FAILURE_THRESHOLD`, or again right away if a trial
+    /// failed.
+    pub fn failed(mut self) {
+        let half_open = std::mem::take(&mut self.trial);
+        let mut circuits = CIRCUITS.lock().unwrap();
+        let circuit = circuits.entry(self.host.clone()).or_default();
+        circuit.consecutive_failures += 1;
+        circuit.trial = false;
+        if half_open || circuit.consecutive_failures >= FAILURE_THRESHOLD {
+            if circuit.opened_at.is_none() || half_open {
+                tracing::warn!(
+                    "circuit for {} opened after {} consecutive failures",
+                    self.host,
+                    circuit.consecutive_failures
+                );
+            }
+            circuit.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+impl Drop for Attempt {
+    fn drop(&mut self) {
+        if self.trial {
+            if let Some(circuit) = CIRCUITS.lock().unwrap().get_mut(&self.host) {
+                circuit.trial = false;
+            }
+        }
+    }
+}
+
+/// Fail fast while `host`'s circuit is open. Once `OPEN_DURATION` has passed
+/// a single trial request is let through; its outcome closes or reopens the
+/// circuit.
+pub fn check(host: &str) -> Result<Attempt, ExtractionError> {
+    let mut circuits = CIRCUITS.lock().unwrap();
+    let circuit = circuits.entry(host.to_string()).or_default();
+    let attempt = |trial| Attempt {
+        host: host.to_string(),
+        trial,
+    };
+    let Some(opened_at) = circuit.opened_at else {
+        return Ok(attempt(false));
+    };
+    let elapsed = opened_at.elapsed();
+    if elapsed < OPEN_DURATION {
+        return Err(ExtractionError::CircuitOpen {
+            host: host.to_string(),
+            retry_after: (OPEN_DURATION - elapsed).as_secs().max(1),
+        });
+    }
+    if circuit.trial {
+        return Err(ExtractionError::CircuitOpen {
+            host: host.to_string(),
+            retry_after: 1,
+        });
+    }
+    circuit.trial = true;
+    Ok(attempt(true))
+}
+
+/// Circuit state of every host fetched so far, for `/health`.
+pub fn snapshot() -> BTreeMap<String, CircuitStatus> {
+    let circuits = CIRCUITS.lock().unwrap();
+    circuits
+        .iter()
+        .map(|(host, circuit)| {
+            let state = match circuit.opened_at {
+                None => CircuitState::Closed,
+                Some(opened_at) if opened_at.elapsed() < OPEN_DURATION => CircuitState::Open,
+                Some(_) => CircuitState::HalfOpen,
+            };
+            let status = CircuitStatus {
+                state,
+                consecutive_failures: circuit.consecutive_failures,
+            };
+            (host.clone(), status)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Let `host`'s open circuit admit a trial request.
+    fn expire(host: &str) {
+        let mut circuits = CIRCUITS.lock().unwrap();
+        let circuit = circuits.get_mut(host).unwrap();
+        circuit.opened_at = Some(Instant::now() - OPEN_DURATION);
+    }
+
+    fn state(host: &str) -> CircuitState {
+        snapshot()[host].state
+    }
+
+    fn open(host: &str) {
+        for _ in 0..FAILURE_THRESHOLD {
+            check(host).unwrap().failed();
+        }
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let host = "opens.test";
+        for _ in 1..FAILURE_THRESHOLD {
+            check(host).unwrap().failed();
+        }
+        assert_eq!(state(host), CircuitState::Closed);
+        check(host).unwrap().failed();
+        assert_eq!(state(host), CircuitState::Open);
+        assert!(matches!(
+            check(host),
+            Err(ExtractionError::CircuitOpen { retry_after, .. }) if retry_after > 1
+        ));
+    }
+
+    #[test]
+    fn non_retried_server_errors_open() {
+        let host = "server-errors.test";
+        for _ in 0..FAILURE_THRESHOLD {
+            check(host)
+                .unwrap()
+                .answered(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        assert_eq!(state(host), CircuitState::Open);
+
+        let host = "not-found.test";
+        for _ in 1..FAILURE_THRESHOLD {
+            check(host).unwrap().answered(StatusCode::NOT_IMPLEMENTED);
+        }
+        check(host).unwrap().answered(StatusCode::NOT_FOUND);
+        assert_eq!(state(host), CircuitState::Closed);
+        assert_eq!(snapshot()[host].consecutive_failures, 0);
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let host = "resets.test";
+        for _ in 1..FAILURE_THRESHOLD {
+            check(host).unwrap().failed();
+        }
+        check(host).unwrap().succeeded();
+        check(host).unwrap().failed();
+        assert_eq!(state(host), CircuitState::Closed);
+        assert_eq!(snapshot()[host].consecutive_failures, 1);
+    }
+
+    #[test]
+    fn half_open_admits_one_trial() {
+        let host = "trial.test";
+        open(host);
+        expire(host);
+        assert_eq!(state(host), CircuitState::HalfOpen);
+
+        let trial = check(host).unwrap();
+        assert!(matches!(
+            check(host),
+            Err(ExtractionError::CircuitOpen { retry_after: 1, .. })
+        ));
+        trial.succeeded();
+        assert_eq!(state(host), CircuitState::Closed);
+        assert!(check(host).is_ok());
+    }
+
+    #[test]
+    fn failed_trial_reopens() {
+        let host = "reopens.test";
+        open(host);
+        expire(host);
+        check(host).unwrap().failed();
+        assert_eq!(state(host), CircuitState::Open);
+        assert!(check(host).is_err());
+    }
+
+    #[test]
+    fn dropped_trial_frees_the_slot() {
+        let host = "dropped.test";
+        open(host);
+        expire(host);
+        drop(check(host).unwrap());
+        assert_eq!(state(host), CircuitState::HalfOpen);
+        assert_eq!(snapshot()[host].consecutive_failures, FAILURE_THRESHOLD);
+        assert!(check(host).is_ok());
+    }
+}
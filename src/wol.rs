@@ -0,0 +1,305 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{ElementRef, Selector};
+use url::Url;
+
+use crate::extract::normalize_text;
+use crate::models::{StudyQuestion, WolData, WolParagraph, WolReference};
+use crate::rules::{Profile, RuleConfig};
+
+// ── Constants ────────────────────────────────────────────────────────────────
+
+/// Classes of inline markers that are not part of the paragraph text.
+const MARKER_CLASSES: &[&str] = &["parNum", "pageNum"];
+
+/// Leading paragraph reference of a study question, e.g. `1, 2.` or `3-5.`
+static QUESTION_REFS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(\d+(?:\s*[,\-–]\s*\d+)*)\s*\.").unwrap());
+
+// ── Built-in rule ────────────────────────────────────────────────────────────
+
+/// Built-in rule for Watchtower Online Library documents. Paragraph numbers,
+/// study questions and page markers are removed from the markdown and
+/// reported as structured data instead.
+pub fn rule_config() -> RuleConfig {
+    let strings = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+    RuleConfig {
+        name: Some("wol".to_string()),
+        host: Some("wol.jw.org".to_string()),
+        profile: Some(Profile::Wol),
+        container_selectors: strings(&["article#article", "#article", "article"]),
+        remove_selectors: strings(&[
+            "p.qu",
+            ".parNum",
+            ".pageNum",
+            ".navLinks",
+            ".documentNavigation",
+            ".resultsNavigation",
+            "#docSubContent",
+            "#toolbar",
+            ".toolbar",
+            ".tabs",
+            ".gen-field",
+            ".footnotes",
+            ".groupFootnote",
+            ".jsPinnedReferences",
+        ]),
+        title_selectors: strings(&["article header h1", "#article h1"]),
+        ..RuleConfig::default()
+    }
+}
+
+// ── Structured data ──────────────────────────────────────────────────────────
+
+/// Collect numbered paragraphs, study questions and reference links from the
+/// WOL container element(s).
+pub fn extract(containers: &[ElementRef<'_>], base_url: &Url) -> WolData {
+    let para_sel = Selector::parse("p[data-pid], p.qu").unwrap();
+    let link_sel = Selector::parse("a[href]").unwrap();
+
+    let mut data = WolData {
+        paragraphs: Vec::new(),
+        study_questions: Vec::new(),
+        references: Vec::new(),
+    };
+
+    for container in containers {
+        for p in container.select(&para_sel) {
+            let pid = p.value().attr("data-pid").and_then(|v| v.parse().ok());
+            let text = normalize_text(text_without_markers(p));
+            if text.is_empty() {
+                continue;
+            }
+
+            if p.value()
+                .has_class("qu", scraper::CaseSensitivity::AsciiCaseInsensitive)
+            {
+                data.study_questions.push(StudyQuestion {
+                    pid,
+                    paragraphs: question_paragraphs(&text),
+                    text,
+                });
+                continue;
+            }
+
+            data.paragraphs.push(WolParagraph {
+                pid,
+                number: paragraph_number(p),
+                text,
+            });
+        }
+
+        for a in container.select(&link_sel) {
+            let href = a.value().attr("href").unwrap_or("");
+            let Ok(url) = base_url.join(href) else {
+                continue;
+            };
+            let Some(kind) = reference_kind(&url) else {
+                continue;
+            };
+            data.references.push(WolReference {
+                kind,
+                text: normalize_text(text_without_markers(a)),
+                url: url.to_string(),
+            });
+        }
+    }
+
+    data
+}
+
+/// Paragraph number from `span.parNum` (or its `data-pnum` attribute).
+fn paragraph_number(p: ElementRef<'_>) -> Option<u32> {
+    let sel = Selector::parse(".parNum").unwrap();
+    let marker = p.select(&sel).next()?;
+    marker
+        .value()
+        .attr("data-pnum")
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| normalize_text(marker.text().collect()))
+        .parse()
+        .ok()
+}
+
+/// Expand the leading `1, 2.` / `3-5.` of a study question into paragraph numbers.
+fn question_paragraphs(text: &str) -> Vec<u32> {
+    let Some(cap) = QUESTION_REFS_RE.captures(text) else {
+        return Vec::new();
+    };
+    let mut numbers = Vec::new();
+    for part in cap[1].split(',') {
+        let bounds: Vec<u32> = part
+            .split(['-', '–'])
+            .filter_map(|n| n.trim().parse().ok())
+            .collect();
+        match bounds.as_slice() {
+            [n] => numbers.push(*n),
+            [start, end] if start <= end => numbers.extend(*start..=*end),
+            _ => {}
+        }
+    }
+    numbers
+}
+
+/// Classify a WOL link by the route segment after `/wol/`: `bc` (Bible
+/// citation), `fn` (footnote), `pc` (publication citation), `d` (document)
+/// and `b` (Bible chapter). Other links are not references.
+fn reference_kind(url: &Url) -> Option<String> {
+    if url.host_str() != Some("wol.jw.org") {
+        return None;
+    }
+    let mut segments = url.path_segments()?;
+    segments.find(|s| *s == "wol")?;
+    let kind = match segments.next()? {
+        "bc" => "scripture",
+        "fn" => "footnote",
+        "pc" => "publication",
+        "d" => "document",
+        "b" => "bible",
+        _ => return None,
+    };
+    Some(kind.to_string())
+}
+
+/// Text of an element, leaving out paragraph and page number markers.
+fn text_without_markers(el: ElementRef<'_>) -> String {
+    use scraper::node::Node;
+    let mut text = String::new();
+    for child in el.children() {
+        match child.value() {
+            Node::Text(t) => text.push_str(&t.text),
+            Node::Element(_) => {
+                if let Some(child_el) = ElementRef::wrap(child) {
+                    let is_marker = MARKER_CLASSES.iter().any(|c| {
+                        child_el
+                            .value()
+                            .has_class(c, scraper::CaseSensitivity::AsciiCaseInsensitive)
+                    });
+                    if !is_marker {
+                        text.push_str(&text_without_markers(child_el));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{extract_from_html, ExtractOptions};
+
+    const URL: &str = "https://wol.jw.org/en/wol/d/r1/lp-e/2024440";
+
+    fn saved_page() -> WolData {
+        extract_from_html(
+            include_str!("../tests/fixtures/wol/study-article.html"),
+            URL,
+            &ExtractOptions::default(),
+        )
+        .unwrap()
+        .wol
+        .unwrap()
+    }
+
+    #[test]
+    fn collects_paragraphs_with_pids_and_numbers() {
+        let data = saved_page();
+        let paragraphs: Vec<(Option<u32>, Option<u32>)> =
+            data.paragraphs.iter().map(|p| (p.pid, p.number)).collect();
+        assert_eq!(
+            paragraphs,
+            [
+                (Some(1), None),
+                (Some(4), Some(1)),
+                (Some(5), Some(2)),
+                (Some(7), Some(3)),
+                (Some(9), None),
+            ]
+        );
+        // Paragraph and page markers are not part of the text.
+        assert_eq!(
+            data.paragraphs[2].text,
+            "Even in trials we can rejoice.* The apostles did so."
+        );
+    }
+
+    #[test]
+    fn expands_study_question_ranges() {
+        let data = saved_page();
+        let questions: Vec<(Option<u32>, &[u32])> = data
+            .study_questions
+            .iter()
+            .map(|q| (q.pid, q.paragraphs.as_slice()))
+            .collect();
+        assert_eq!(
+            questions,
+            [
+                (Some(3), &[1, 2][..]),
+                (Some(6), &[3, 5][..]),
+                (Some(8), &[4, 5, 6][..]),
+            ]
+        );
+        assert_eq!(
+            data.study_questions[1].text,
+            "3, 5. What helps us keep our joy?"
+        );
+    }
+
+    #[test]
+    fn classifies_references() {
+        let data = saved_page();
+        let references: Vec<(&str, &str)> = data
+            .references
+            .iter()
+            .map(|r| (r.kind.as_str(), r.text.as_str()))
+            .collect();
+        assert_eq!(
+            references,
+            [
+                ("scripture", "Galatians 5:22"),
+                ("footnote", "*"),
+                ("publication", "the article"),
+                ("document", "“Be Joyful”"),
+                ("bible", "Psalm 100"),
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_markers_and_questions_from_markdown() {
+        let markdown = extract_from_html(
+            include_str!("../tests/fixtures/wol/study-article.html"),
+            URL,
+            &ExtractOptions::default(),
+        )
+        .unwrap()
+        .markdown;
+        assert!(markdown.starts_with("# Keep Your Joy\n\nJoy is a fruit"));
+        assert!(!markdown.contains("Why do we need joy"));
+        assert!(!markdown.contains("Home"));
+        assert!(!markdown.contains("Next"));
+    }
+
+    #[test]
+    fn question_paragraphs_edge_cases() {
+        assert_eq!(question_paragraphs("7. Why?"), [7]);
+        assert_eq!(question_paragraphs("1, 3-4. Why?"), [1, 3, 4]);
+        assert_eq!(question_paragraphs("5-3. Why?"), [] as [u32; 0]);
+        assert_eq!(question_paragraphs("Why? 1-2."), [] as [u32; 0]);
+    }
+
+    #[test]
+    fn reference_kind_needs_wol_host_and_route() {
+        let kind = |u: &str| reference_kind(&Url::parse(u).unwrap());
+        assert_eq!(
+            kind("https://wol.jw.org/es/wol/bc/r4/lp-s/1/2").as_deref(),
+            Some("scripture")
+        );
+        assert_eq!(kind("https://wol.jw.org/en/wol/h/r1/lp-e"), None);
+        assert_eq!(kind("https://www.jw.org/en/wol/d/r1/lp-e/1"), None);
+        assert_eq!(kind("https://wol.jw.org/en/library/d/1"), None);
+    }
+}